@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rusty_drones::codec::{decode, encode};
+
+fuzz_target!(|data: &[u8]| {
+    // Any packet that decodes must encode back to the same bytes
+    if let Ok(packet) = decode(data) {
+        assert_eq!(encode(&packet).as_deref(), Ok(data));
+    }
+});
@@ -0,0 +1,285 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use crossbeam_channel::{unbounded, Receiver};
+use libfuzzer_sys::fuzz_target;
+use rusty_drones::RustyDrone;
+use std::collections::HashMap;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{
+    Ack, FloodRequest, FloodResponse, Fragment, Nack, NackType, NodeType, Packet, PacketType,
+    FRAGMENT_DSIZE,
+};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    id: NodeId,
+    seed: u64,
+    pdr: u8,
+    neighbors: Vec<NodeId>,
+    actions: Vec<Action>,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Action {
+    Packet(FuzzPacket),
+    Command(FuzzCommand),
+}
+
+#[derive(Arbitrary, Debug)]
+enum FuzzCommand {
+    /// Add a neighbor, optionally one that already dropped its receiving channel.
+    AddSender { node_id: NodeId, dead: bool },
+    RemoveSender(NodeId),
+    SetPacketDropRate(f32),
+    Crash,
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzPacket {
+    hops: Vec<NodeId>,
+    hop_index: usize,
+    session_id: u64,
+    kind: FuzzKind,
+}
+
+#[derive(Arbitrary, Debug)]
+enum FuzzKind {
+    Fragment {
+        fragment_index: u64,
+        total_n_fragments: u64,
+        length: u8,
+    },
+    Ack(u64),
+    Nack {
+        fragment_index: u64,
+        nack_type: FuzzNackType,
+    },
+    FloodRequest {
+        flood_id: u64,
+        initiator_id: NodeId,
+        path_trace: Vec<(NodeId, FuzzNodeType)>,
+    },
+    FloodResponse {
+        flood_id: u64,
+        path_trace: Vec<(NodeId, FuzzNodeType)>,
+    },
+}
+
+#[derive(Arbitrary, Debug)]
+enum FuzzNackType {
+    ErrorInRouting(NodeId),
+    DestinationIsDrone,
+    Dropped,
+    UnexpectedRecipient(NodeId),
+}
+
+#[derive(Arbitrary, Debug, Clone, Copy)]
+enum FuzzNodeType {
+    Client,
+    Drone,
+    Server,
+}
+
+impl From<FuzzNodeType> for NodeType {
+    fn from(node_type: FuzzNodeType) -> Self {
+        match node_type {
+            FuzzNodeType::Client => NodeType::Client,
+            FuzzNodeType::Drone => NodeType::Drone,
+            FuzzNodeType::Server => NodeType::Server,
+        }
+    }
+}
+
+impl From<FuzzNackType> for NackType {
+    fn from(nack_type: FuzzNackType) -> Self {
+        match nack_type {
+            FuzzNackType::ErrorInRouting(id) => NackType::ErrorInRouting(id),
+            FuzzNackType::DestinationIsDrone => NackType::DestinationIsDrone,
+            FuzzNackType::Dropped => NackType::Dropped,
+            FuzzNackType::UnexpectedRecipient(id) => NackType::UnexpectedRecipient(id),
+        }
+    }
+}
+
+fn path_trace(path: Vec<(NodeId, FuzzNodeType)>) -> Vec<(NodeId, NodeType)> {
+    path.into_iter().map(|(id, t)| (id, t.into())).collect()
+}
+
+impl From<FuzzPacket> for Packet {
+    fn from(packet: FuzzPacket) -> Self {
+        let pack_type = match packet.kind {
+            FuzzKind::Fragment {
+                fragment_index,
+                total_n_fragments,
+                length,
+            } => PacketType::MsgFragment(Fragment {
+                fragment_index,
+                total_n_fragments,
+                length,
+                data: [length; FRAGMENT_DSIZE],
+            }),
+            FuzzKind::Ack(fragment_index) => PacketType::Ack(Ack { fragment_index }),
+            FuzzKind::Nack {
+                fragment_index,
+                nack_type,
+            } => PacketType::Nack(Nack {
+                fragment_index,
+                nack_type: nack_type.into(),
+            }),
+            FuzzKind::FloodRequest {
+                flood_id,
+                initiator_id,
+                path_trace: path,
+            } => PacketType::FloodRequest(FloodRequest {
+                flood_id,
+                initiator_id,
+                path_trace: path_trace(path),
+            }),
+            FuzzKind::FloodResponse {
+                flood_id,
+                path_trace: path,
+            } => PacketType::FloodResponse(FloodResponse {
+                flood_id,
+                path_trace: path_trace(path),
+            }),
+        };
+
+        Packet {
+            routing_header: SourceRoutingHeader {
+                hop_index: packet.hop_index,
+                hops: packet.hops,
+            },
+            session_id: packet.session_id,
+            pack_type,
+        }
+    }
+}
+
+/// Everything the drone produced while handling a single packet.
+#[derive(Default)]
+struct Output {
+    /// Packets received by each neighbor.
+    sent: Vec<(NodeId, Packet)>,
+    events: Vec<DroneEvent>,
+}
+
+fn collect(neighbors: &HashMap<NodeId, Receiver<Packet>>, events: &Receiver<DroneEvent>) -> Output {
+    let mut output = Output::default();
+    for (node_id, receiver) in neighbors {
+        output
+            .sent
+            .extend(receiver.try_iter().map(|packet| (*node_id, packet)));
+    }
+    output.events.extend(events.try_iter());
+    output
+}
+
+/// Every packet handed to a neighbor must have that neighbor as its current hop.
+fn check_forwarded_routing(output: &Output) {
+    for (node_id, packet) in &output.sent {
+        if matches!(packet.pack_type, PacketType::FloodRequest(_)) {
+            continue;
+        }
+
+        let routing = &packet.routing_header;
+        assert!(
+            routing.hop_index < routing.hops.len(),
+            "forwarded packet with out of range hop_index: {packet:?}"
+        );
+        assert_eq!(
+            routing.hops[routing.hop_index], *node_id,
+            "forwarded packet to a node that is not its current hop: {packet:?}"
+        );
+    }
+}
+
+/// A fragment must be either forwarded, nacked (dropping is also a nack) or discarded as malformed.
+fn check_fragment_outcome(output: &Output, malformed: u64) {
+    let forwards = output
+        .sent
+        .iter()
+        .filter(|(_, p)| matches!(p.pack_type, PacketType::MsgFragment(_)))
+        .count();
+    let sent_nacks = output
+        .sent
+        .iter()
+        .filter(|(_, p)| matches!(p.pack_type, PacketType::Nack(_)))
+        .count();
+    let shortcut_nacks = output
+        .events
+        .iter()
+        .filter(|e| {
+            matches!(e, DroneEvent::ControllerShortcut(p) if matches!(p.pack_type, PacketType::Nack(_)))
+        })
+        .count();
+    let drops = output
+        .events
+        .iter()
+        .filter(|e| matches!(e, DroneEvent::PacketDropped(_)))
+        .count();
+
+    let outcomes = forwards + sent_nacks + shortcut_nacks + usize::try_from(malformed).unwrap();
+    assert_eq!(outcomes, 1, "fragment handled {outcomes} times");
+    assert!(drops <= 1, "fragment dropped {drops} times");
+}
+
+fuzz_target!(|input: Input| {
+    let (controller_send, event_recv) = unbounded::<DroneEvent>();
+    let (_command_send, controller_recv) = unbounded::<DroneCommand>();
+    let (_packet_send, packet_recv) = unbounded::<Packet>();
+
+    let mut neighbors = HashMap::new();
+    let mut builder = RustyDrone::builder()
+        .id(input.id)
+        .controller_send(controller_send)
+        .controller_recv(controller_recv)
+        .packet_recv(packet_recv)
+        .pdr(f32::from(input.pdr) / f32::from(u8::MAX))
+        .seed(input.seed);
+    for node_id in input.neighbors {
+        let (sender, receiver) = unbounded();
+        neighbors.insert(node_id, receiver);
+        builder = builder.neighbor(node_id, sender);
+    }
+
+    let mut drone = builder.build().unwrap();
+    let stats = drone.stats();
+    let mut crashed = false;
+
+    for action in input.actions {
+        match action {
+            Action::Command(command) => {
+                let command = match command {
+                    FuzzCommand::AddSender { node_id, dead } => {
+                        let (sender, receiver) = unbounded();
+                        if dead {
+                            neighbors.remove(&node_id);
+                        } else {
+                            neighbors.insert(node_id, receiver);
+                        }
+                        DroneCommand::AddSender(node_id, sender)
+                    }
+                    FuzzCommand::RemoveSender(node_id) => DroneCommand::RemoveSender(node_id),
+                    FuzzCommand::SetPacketDropRate(pdr) => DroneCommand::SetPacketDropRate(pdr),
+                    FuzzCommand::Crash => DroneCommand::Crash,
+                };
+                crashed |= drone.fuzz_handle_commands(&command);
+            }
+            Action::Packet(packet) => {
+                let packet = Packet::from(packet);
+                let malformed_before = stats.snapshot().malformed.values().sum::<u64>();
+
+                drone.fuzz_handle_packet(&packet, crashed);
+
+                let malformed = stats.snapshot().malformed.values().sum::<u64>() - malformed_before;
+                let output = collect(&neighbors, &event_recv);
+                check_forwarded_routing(&output);
+                if matches!(packet.pack_type, PacketType::MsgFragment(_)) {
+                    check_fragment_outcome(&output, malformed);
+                }
+            }
+        }
+    }
+});
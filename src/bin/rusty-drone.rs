@@ -0,0 +1,42 @@
+#![warn(clippy::pedantic)]
+//! Run a single `RustyDrone` as its own process, controlled by a launcher.
+//!
+//! ```text
+//! rusty-drone --id <id> --control <address> [options]
+//! ```
+//! Normally started by `rusty_drones::process::Launcher`, not by hand.
+
+use rusty_drones::process::DroneProcess;
+use std::env;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: rusty-drone --id <id> --control <address> [options]
+options:
+  --pdr <f32>         packet drop rate (default 0)
+  --listen <address>  address to receive packets on (default 127.0.0.1:0)
+  --seed <u64>        simulation seed (default from the system)
+  --drain <ms>        time to handle queued packets after crashing (default 100)";
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        eprintln!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let process = match DroneProcess::from_args(args) {
+        Ok(process) => process,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match process.run() {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,266 @@
+#![warn(clippy::pedantic)]
+//! Run a scenario on a network of `RustyDrone`s, with a headless Simulation Controller.
+//!
+//! ```text
+//! rusty-sim <network.toml> <script> [options]
+//! rusty-sim [<network.toml>] <scenario.toml> [options]
+//! ```
+//! A `.toml` scenario can describe its own network, otherwise the network file is required.
+//! With a `.toml` scenario the exit code is a failure if any expectation is not met.
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use rusty_drones::simulation::{
+    load_config, ChromeTrace, Controller, DotGraph, EventRecord, Journeys, Network, Recorder,
+    Scenario, Script, Topology,
+};
+use rusty_drones::{Capture, DroneBuilder, PacketSpan};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io::{stdout, BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
+
+const USAGE: &str = "usage: rusty-sim <network.toml> <script> [options]
+       rusty-sim [<network.toml>] <scenario.toml> [options]
+options:
+  --seed <u64>      simulation seed (default 0, or the one of the scenario)
+  --settle <ms>     wait time without activity before stopping (default 200)
+  --output <file>   save the events to a file instead of printing them
+  --dot <file>      save the final network links as a Graphviz DOT graph
+  --record <file>   record the events as JSON Lines while the simulation runs
+  --journeys        print the hops of every fragment after the events
+  --trace <file>    save the packets handled by the drones as a Chrome trace (for Perfetto)
+  --capture <file>  save every packet received and sent by the drones in a capture file";
+
+struct Args {
+    network: Option<String>,
+    scenario: String,
+    seed: Option<u64>,
+    settle: Option<u64>,
+    output: Option<String>,
+    dot: Option<String>,
+    record: Option<String>,
+    journeys: bool,
+    trace: Option<String>,
+    capture: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut positional = vec![];
+    let mut seed = None;
+    let mut settle = None;
+    let mut output = None;
+    let mut dot = None;
+    let mut record = None;
+    let mut journeys = false;
+    let mut trace = None;
+    let mut capture = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for `{arg}`"));
+        match arg.as_str() {
+            "--seed" => seed = Some(value()?.parse().map_err(|_| "invalid seed")?),
+            "--settle" => settle = Some(value()?.parse().map_err(|_| "invalid settle time")?),
+            "--output" => output = Some(value()?),
+            "--dot" => dot = Some(value()?),
+            "--record" => record = Some(value()?),
+            "--journeys" => journeys = true,
+            "--trace" => trace = Some(value()?),
+            "--capture" => capture = Some(value()?),
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{arg}`")),
+            _ => positional.push(arg),
+        }
+    }
+
+    let (network, scenario) = match <[String; 2]>::try_from(positional) {
+        Ok([network, scenario]) => (Some(network), scenario),
+        Err(mut positional) if positional.len() == 1 => (None, positional.remove(0)),
+        Err(_) => return Err(USAGE.to_string()),
+    };
+    Ok(Args {
+        network,
+        scenario,
+        seed,
+        settle,
+        output,
+        dot,
+        record,
+        journeys,
+        trace,
+        capture,
+    })
+}
+
+/// Run a line based script on the network file.
+fn run_script(args: &Args) -> Result<bool, Box<dyn Error>> {
+    let Some(ref network) = args.network else {
+        return Err("a script needs a network file".into());
+    };
+    let topology = Topology::from_config(&load_config(network)?);
+    let script = Script::from_file(&args.scenario)?;
+    let seed = args.seed.unwrap_or_default();
+    let taps = Taps::new(args)?;
+    let network = Network::spawn_with(&topology, |_, builder| {
+        taps.configure(builder).simulation_seed(seed)
+    })?;
+
+    let mut controller = Controller::new(network);
+    start_recording(args, &mut controller)?;
+    let settle = Duration::from_millis(args.settle.unwrap_or(200));
+    let result = controller.run_script(&script, settle);
+    finish_recording(&mut controller)?;
+
+    write_events(args, &controller)?;
+    write_dot(args, &controller)?;
+    taps.finish(args, &controller)?;
+    println!("{}", controller.summary());
+    controller.into_network().join();
+    result?;
+    Ok(true)
+}
+
+/// Run a TOML scenario, optionally on the network file.
+/// # Returns
+/// If every expectation was met.
+fn run_scenario(args: &Args) -> Result<bool, Box<dyn Error>> {
+    let mut scenario = Scenario::from_file(&args.scenario)?;
+    if let Some(ref network) = args.network {
+        scenario.set_network(load_config(network)?);
+    }
+    scenario.seed = args.seed.or(scenario.seed);
+    scenario.settle_ms = args.settle.unwrap_or(scenario.settle_ms);
+
+    let taps = Taps::new(args)?;
+    let mut controller = scenario.start_with(|_, builder| taps.configure(builder))?;
+    start_recording(args, &mut controller)?;
+    let result = scenario.run_script(&mut controller);
+    finish_recording(&mut controller)?;
+    let report = scenario.evaluate(&controller);
+
+    write_events(args, &controller)?;
+    write_dot(args, &controller)?;
+    taps.finish(args, &controller)?;
+    println!("{report}");
+    controller.into_network().join();
+    result?;
+    Ok(report.passed())
+}
+
+fn start_recording(args: &Args, controller: &mut Controller) -> Result<(), Box<dyn Error>> {
+    if let Some(ref path) = args.record {
+        controller.record(Recorder::create(path)?);
+    }
+    Ok(())
+}
+
+fn finish_recording(controller: &mut Controller) -> Result<(), Box<dyn Error>> {
+    if let Some(recorder) = controller.take_recorder() {
+        recorder.finish()?;
+    }
+    Ok(())
+}
+
+fn write_events(args: &Args, controller: &Controller) -> Result<(), Box<dyn Error>> {
+    let mut log: Box<dyn Write> = match args.output {
+        Some(ref path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(stdout().lock()),
+    };
+    for event in controller.events() {
+        writeln!(log, "{event}")?;
+    }
+    if args.journeys {
+        let records = controller
+            .events()
+            .iter()
+            .map(EventRecord::from)
+            .collect::<Vec<_>>();
+        let journeys = Journeys::from_records(&records);
+        if !journeys.is_empty() {
+            writeln!(log, "{journeys}")?;
+        }
+    }
+    log.flush()?;
+    Ok(())
+}
+
+fn write_dot(args: &Args, controller: &Controller) -> Result<(), Box<dyn Error>> {
+    if let Some(ref path) = args.dot {
+        fs::write(path, DotGraph::from_network(controller.network()).to_dot())?;
+    }
+    Ok(())
+}
+
+/// What the drones report besides their events: packet spans and captured packets.
+struct Taps {
+    spans: Option<(Sender<PacketSpan>, Receiver<PacketSpan>)>,
+    capture: Option<Capture>,
+}
+
+impl Taps {
+    fn new(args: &Args) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            spans: args.trace.as_ref().map(|_| unbounded()),
+            capture: args.capture.as_ref().map(Capture::create).transpose()?,
+        })
+    }
+
+    fn configure(&self, mut builder: DroneBuilder) -> DroneBuilder {
+        if let Some((ref span_send, _)) = self.spans {
+            builder = builder.span_send(span_send.clone());
+        }
+        if let Some(ref capture) = self.capture {
+            builder = builder.capture(capture.clone());
+        }
+        builder
+    }
+
+    /// Write the trace and flush the capture.
+    fn finish(&self, args: &Args, controller: &Controller) -> Result<(), Box<dyn Error>> {
+        if let (Some(ref path), Some((_, ref span_recv))) = (&args.trace, &self.spans) {
+            let mut trace = ChromeTrace::new();
+            trace
+                .add_spans(span_recv.try_iter())
+                .add_events(controller.events());
+            let mut file = BufWriter::new(File::create(path)?);
+            trace.write(&mut file)?;
+            file.flush()?;
+        }
+        if let Some(ref capture) = self.capture {
+            capture.finish()?;
+        }
+        Ok(())
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let toml = Path::new(&args.scenario)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+    let result = if toml {
+        run_scenario(&args)
+    } else {
+        run_script(&args)
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
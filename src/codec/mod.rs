@@ -0,0 +1,353 @@
+//! Stable binary encoding of `wg_2024` packets, for captures and socket transports.
+//!
+//! Every packet is a frame: the format version (u8), the length of the body (u32) and the body.
+//! The body is the session id (u64), the hop index (u64), the hops (u16 count, one byte each)
+//! and the packet type (u8 tag) followed by its fields. Lists are prefixed by a u16 count and
+//! numbers are little endian. A fragment always carries its 128 bytes of data.
+
+mod test;
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{
+    Ack, FloodRequest, FloodResponse, Fragment, Nack, NackType, NodeType, Packet, PacketType,
+    FRAGMENT_DSIZE,
+};
+
+/// Version of the format written by [`encode`].
+pub const VERSION: u8 = 1;
+/// Bytes before the body of a frame: version and body length.
+pub const HEADER_LEN: usize = 5;
+/// Longest frame, so that every packet fits in a single UDP datagram.
+pub const MAX_FRAME_LEN: usize = 65_507;
+
+/// Reason why a packet cannot be encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The fragment says it uses more bytes than its data has.
+    InvalidFragmentLength(u8),
+    /// The frame would be longer than [`MAX_FRAME_LEN`].
+    TooLong(usize),
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodeError::InvalidFragmentLength(length) => {
+                write!(f, "fragment length {length} is more than {FRAGMENT_DSIZE}")
+            }
+            EncodeError::TooLong(len) => {
+                write!(f, "frame of {len} bytes is longer than {MAX_FRAME_LEN}")
+            }
+        }
+    }
+}
+
+impl Error for EncodeError {}
+
+/// Reason why bytes cannot be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The bytes ended in the middle of a frame.
+    UnexpectedEnd,
+    UnsupportedVersion(u8),
+    /// The length of the frame is more than [`MAX_FRAME_LEN`].
+    TooLong(usize),
+    /// Unknown variant of the named enum.
+    InvalidTag(&'static str, u8),
+    /// The fragment says it uses more bytes than its data has.
+    InvalidFragmentLength(u8),
+    /// The body of the frame has bytes after the packet.
+    TrailingBytes(usize),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of data"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported encoding version {version}")
+            }
+            DecodeError::TooLong(len) => {
+                write!(f, "frame of {len} bytes is longer than {MAX_FRAME_LEN}")
+            }
+            DecodeError::InvalidTag(name, tag) => write!(f, "invalid {name} tag {tag}"),
+            DecodeError::InvalidFragmentLength(length) => {
+                write!(f, "fragment length {length} is more than {FRAGMENT_DSIZE}")
+            }
+            DecodeError::TrailingBytes(n) => write!(f, "{n} bytes after the packet"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// Encode the packet as a frame.
+/// # Errors
+/// If the fragment length is more than its data or the frame is too long.
+pub fn encode(packet: &Packet) -> Result<Vec<u8>, EncodeError> {
+    let mut out = vec![];
+    encode_into(packet, &mut out)?;
+    Ok(out)
+}
+
+/// Append the frame of the packet to `out`, which is left unchanged on error.
+/// # Errors
+/// If the fragment length is more than its data or the frame is too long.
+pub fn encode_into(packet: &Packet, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+    let start = out.len();
+    out.push(VERSION);
+    out.extend_from_slice(&[0; 4]);
+
+    let body = encode_body(packet, out);
+    let len = out.len() - start;
+    let result = body.and_then(|()| {
+        if len > MAX_FRAME_LEN {
+            return Err(EncodeError::TooLong(len));
+        }
+        Ok(())
+    });
+    if result.is_err() {
+        out.truncate(start);
+        return result;
+    }
+
+    // Fits in a u32, as it is at most MAX_FRAME_LEN
+    let body_len = u32::try_from(len - HEADER_LEN).unwrap_or(u32::MAX);
+    out[start + 1..start + HEADER_LEN].copy_from_slice(&body_len.to_le_bytes());
+    Ok(())
+}
+
+/// Decode a buffer holding exactly one frame.
+/// # Errors
+/// If the bytes are not a valid frame, or have bytes after it.
+pub fn decode(bytes: &[u8]) -> Result<Packet, DecodeError> {
+    let (packet, used) = decode_prefix(bytes)?;
+    if used != bytes.len() {
+        return Err(DecodeError::TrailingBytes(bytes.len() - used));
+    }
+    Ok(packet)
+}
+
+/// Decode the frame at the start of the buffer.
+/// # Returns
+/// The packet and the length of its frame.
+/// # Errors
+/// If the bytes do not start with a valid frame.
+pub fn decode_prefix(bytes: &[u8]) -> Result<(Packet, usize), DecodeError> {
+    let header = bytes.get(..HEADER_LEN).ok_or(DecodeError::UnexpectedEnd)?;
+    let len = frame_len(header)?;
+    let body = bytes
+        .get(HEADER_LEN..len)
+        .ok_or(DecodeError::UnexpectedEnd)?;
+
+    let mut input = Input { bytes: body };
+    let packet = input.packet()?;
+    if !input.bytes.is_empty() {
+        return Err(DecodeError::TrailingBytes(input.bytes.len()));
+    }
+    Ok((packet, len))
+}
+
+/// Length of the whole frame starting with `header`, to know how much to read from a stream.
+/// # Errors
+/// If the header is too short, of another version or the frame is too long.
+pub fn frame_len(header: &[u8]) -> Result<usize, DecodeError> {
+    let header = header.get(..HEADER_LEN).ok_or(DecodeError::UnexpectedEnd)?;
+    if header[0] != VERSION {
+        return Err(DecodeError::UnsupportedVersion(header[0]));
+    }
+
+    let mut body_len = [0; 4];
+    body_len.copy_from_slice(&header[1..]);
+    let len = usize::try_from(u32::from_le_bytes(body_len))
+        .map_or(usize::MAX, |body_len| body_len.saturating_add(HEADER_LEN));
+    if len > MAX_FRAME_LEN {
+        return Err(DecodeError::TooLong(len));
+    }
+    Ok(len)
+}
+
+fn encode_body(packet: &Packet, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+    let routing = &packet.routing_header;
+    put_u64(out, packet.session_id);
+    put_u64(out, routing.hop_index as u64);
+    put_count(out, routing.hops.len())?;
+    out.extend_from_slice(&routing.hops);
+
+    match &packet.pack_type {
+        PacketType::MsgFragment(fragment) => {
+            if usize::from(fragment.length) > FRAGMENT_DSIZE {
+                return Err(EncodeError::InvalidFragmentLength(fragment.length));
+            }
+            out.push(0);
+            put_u64(out, fragment.fragment_index);
+            put_u64(out, fragment.total_n_fragments);
+            out.push(fragment.length);
+            out.extend_from_slice(&fragment.data);
+        }
+        PacketType::Ack(ack) => {
+            out.push(1);
+            put_u64(out, ack.fragment_index);
+        }
+        PacketType::Nack(nack) => {
+            out.push(2);
+            put_u64(out, nack.fragment_index);
+            match nack.nack_type {
+                NackType::ErrorInRouting(id) => out.extend_from_slice(&[0, id]),
+                NackType::DestinationIsDrone => out.push(1),
+                NackType::Dropped => out.push(2),
+                NackType::UnexpectedRecipient(id) => out.extend_from_slice(&[3, id]),
+            }
+        }
+        PacketType::FloodRequest(flood) => {
+            out.push(3);
+            put_u64(out, flood.flood_id);
+            out.push(flood.initiator_id);
+            put_path(out, &flood.path_trace)?;
+        }
+        PacketType::FloodResponse(flood) => {
+            out.push(4);
+            put_u64(out, flood.flood_id);
+            put_path(out, &flood.path_trace)?;
+        }
+    }
+    Ok(())
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_count(out: &mut Vec<u8>, count: usize) -> Result<(), EncodeError> {
+    // A longer list cannot fit in a frame anyway
+    let count = u16::try_from(count).map_err(|_| EncodeError::TooLong(count))?;
+    out.extend_from_slice(&count.to_le_bytes());
+    Ok(())
+}
+
+fn put_path(out: &mut Vec<u8>, path: &[(NodeId, NodeType)]) -> Result<(), EncodeError> {
+    put_count(out, path.len())?;
+    for (id, node_type) in path {
+        let node_type = match node_type {
+            NodeType::Client => 0,
+            NodeType::Drone => 1,
+            NodeType::Server => 2,
+        };
+        out.extend_from_slice(&[*id, node_type]);
+    }
+    Ok(())
+}
+
+/// Body being decoded, without the bytes already read.
+struct Input<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Input<'a> {
+    fn packet(&mut self) -> Result<Packet, DecodeError> {
+        let session_id = self.u64()?;
+        let hop_index = usize::try_from(self.u64()?).map_err(|_| DecodeError::UnexpectedEnd)?;
+        let hops_len = self.count()?;
+        let hops = self.take(hops_len)?.to_vec();
+
+        let pack_type = match self.u8()? {
+            0 => PacketType::MsgFragment(self.fragment()?),
+            1 => PacketType::Ack(Ack {
+                fragment_index: self.u64()?,
+            }),
+            2 => PacketType::Nack(self.nack()?),
+            3 => PacketType::FloodRequest(FloodRequest {
+                flood_id: self.u64()?,
+                initiator_id: self.u8()?,
+                path_trace: self.path()?,
+            }),
+            4 => PacketType::FloodResponse(FloodResponse {
+                flood_id: self.u64()?,
+                path_trace: self.path()?,
+            }),
+            tag => return Err(DecodeError::InvalidTag("packet type", tag)),
+        };
+
+        Ok(Packet {
+            routing_header: SourceRoutingHeader::new(hops, hop_index),
+            session_id,
+            pack_type,
+        })
+    }
+
+    fn fragment(&mut self) -> Result<Fragment, DecodeError> {
+        let fragment_index = self.u64()?;
+        let total_n_fragments = self.u64()?;
+        let length = self.u8()?;
+        if usize::from(length) > FRAGMENT_DSIZE {
+            return Err(DecodeError::InvalidFragmentLength(length));
+        }
+        let mut data = [0; FRAGMENT_DSIZE];
+        data.copy_from_slice(self.take(FRAGMENT_DSIZE)?);
+
+        Ok(Fragment {
+            fragment_index,
+            total_n_fragments,
+            length,
+            data,
+        })
+    }
+
+    fn nack(&mut self) -> Result<Nack, DecodeError> {
+        let fragment_index = self.u64()?;
+        let nack_type = match self.u8()? {
+            0 => NackType::ErrorInRouting(self.u8()?),
+            1 => NackType::DestinationIsDrone,
+            2 => NackType::Dropped,
+            3 => NackType::UnexpectedRecipient(self.u8()?),
+            tag => return Err(DecodeError::InvalidTag("nack type", tag)),
+        };
+        Ok(Nack {
+            fragment_index,
+            nack_type,
+        })
+    }
+
+    fn path(&mut self) -> Result<Vec<(NodeId, NodeType)>, DecodeError> {
+        let len = self.count()?;
+        let mut path = Vec::with_capacity(len.min(self.bytes.len() / 2));
+        for _ in 0..len {
+            let id = self.u8()?;
+            let node_type = match self.u8()? {
+                0 => NodeType::Client,
+                1 => NodeType::Drone,
+                2 => NodeType::Server,
+                tag => return Err(DecodeError::InvalidTag("node type", tag)),
+            };
+            path.push((id, node_type));
+        }
+        Ok(path)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        if n > self.bytes.len() {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn count(&mut self) -> Result<usize, DecodeError> {
+        let mut bytes = [0; 2];
+        bytes.copy_from_slice(self.take(2)?);
+        Ok(usize::from(u16::from_le_bytes(bytes)))
+    }
+}
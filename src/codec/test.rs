@@ -0,0 +1,208 @@
+#![cfg(test)]
+
+use crate::codec::{
+    decode, decode_prefix, encode, encode_into, frame_len, DecodeError, EncodeError, HEADER_LEN,
+    MAX_FRAME_LEN, VERSION,
+};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{
+    Ack, FloodRequest, FloodResponse, Fragment, Nack, NackType, NodeType, Packet, PacketType,
+    FRAGMENT_DSIZE,
+};
+
+fn fragment_packet(length: u8) -> Packet {
+    Packet::new_fragment(
+        SourceRoutingHeader::new(vec![1, 11, 21], 1),
+        7,
+        Fragment {
+            fragment_index: 2,
+            total_n_fragments: 3,
+            length,
+            data: [5; FRAGMENT_DSIZE],
+        },
+    )
+}
+
+fn node_type() -> impl Strategy<Value = NodeType> {
+    prop_oneof![
+        Just(NodeType::Client),
+        Just(NodeType::Drone),
+        Just(NodeType::Server)
+    ]
+}
+
+fn path() -> impl Strategy<Value = Vec<(NodeId, NodeType)>> {
+    vec((any::<NodeId>(), node_type()), 0..20)
+}
+
+fn nack_type() -> impl Strategy<Value = NackType> {
+    prop_oneof![
+        any::<NodeId>().prop_map(NackType::ErrorInRouting),
+        Just(NackType::DestinationIsDrone),
+        Just(NackType::Dropped),
+        any::<NodeId>().prop_map(NackType::UnexpectedRecipient),
+    ]
+}
+
+fn pack_type() -> impl Strategy<Value = PacketType> {
+    prop_oneof![
+        (
+            any::<u64>(),
+            any::<u64>(),
+            0..=128u8,
+            vec(any::<u8>(), FRAGMENT_DSIZE)
+        )
+            .prop_map(|(fragment_index, total_n_fragments, length, data)| {
+                let mut fragment = Fragment {
+                    fragment_index,
+                    total_n_fragments,
+                    length,
+                    data: [0; FRAGMENT_DSIZE],
+                };
+                fragment.data.copy_from_slice(&data);
+                PacketType::MsgFragment(fragment)
+            }),
+        any::<u64>().prop_map(|fragment_index| PacketType::Ack(Ack { fragment_index })),
+        (any::<u64>(), nack_type()).prop_map(|(fragment_index, nack_type)| {
+            PacketType::Nack(Nack {
+                fragment_index,
+                nack_type,
+            })
+        }),
+        (any::<u64>(), any::<NodeId>(), path()).prop_map(|(flood_id, initiator_id, path_trace)| {
+            PacketType::FloodRequest(FloodRequest {
+                flood_id,
+                initiator_id,
+                path_trace,
+            })
+        }),
+        (any::<u64>(), path()).prop_map(|(flood_id, path_trace)| {
+            PacketType::FloodResponse(FloodResponse {
+                flood_id,
+                path_trace,
+            })
+        }),
+    ]
+}
+
+fn packet() -> impl Strategy<Value = Packet> {
+    (
+        vec(any::<NodeId>(), 0..20),
+        any::<usize>(),
+        any::<u64>(),
+        pack_type(),
+    )
+        .prop_map(|(hops, hop_index, session_id, pack_type)| Packet {
+            routing_header: SourceRoutingHeader::new(hops, hop_index),
+            session_id,
+            pack_type,
+        })
+}
+
+proptest! {
+    #[test]
+    fn prop_codec_round_trip(packet in packet()) {
+        let bytes = encode(&packet).unwrap();
+
+        prop_assert_eq!(frame_len(&bytes), Ok(bytes.len()));
+        prop_assert_eq!(decode(&bytes), Ok(packet));
+    }
+
+    #[test]
+    fn prop_codec_frames_in_a_row(first in packet(), second in packet()) {
+        let mut bytes = vec![];
+        encode_into(&first, &mut bytes).unwrap();
+        encode_into(&second, &mut bytes).unwrap();
+
+        let (decoded, used) = decode_prefix(&bytes).unwrap();
+        prop_assert_eq!(decoded, first);
+        prop_assert_eq!(decode(&bytes[used..]), Ok(second));
+    }
+
+    #[test]
+    fn prop_codec_truncated(packet in packet(), cut in any::<prop::sample::Index>()) {
+        let bytes = encode(&packet).unwrap();
+        let cut = cut.index(bytes.len());
+
+        prop_assert_eq!(decode(&bytes[..cut]), Err(DecodeError::UnexpectedEnd));
+    }
+}
+
+#[test]
+fn test_codec_layout() {
+    let packet = Packet::new_ack(SourceRoutingHeader::new(vec![21, 12], 1), 7, 2);
+    let mut expected = vec![VERSION, 29, 0, 0, 0];
+    expected.extend_from_slice(&7u64.to_le_bytes());
+    expected.extend_from_slice(&1u64.to_le_bytes());
+    expected.extend_from_slice(&[2, 0, 21, 12, 1]);
+    expected.extend_from_slice(&2u64.to_le_bytes());
+
+    assert_eq!(encode(&packet), Ok(expected));
+}
+
+#[test]
+fn test_codec_fragment_length() {
+    let full = fragment_packet(128);
+    let bytes = encode(&full).unwrap();
+    assert_eq!(decode(&bytes), Ok(full));
+
+    let too_long = fragment_packet(129);
+    let mut out = vec![1, 2, 3];
+    assert_eq!(
+        encode_into(&too_long, &mut out),
+        Err(EncodeError::InvalidFragmentLength(129))
+    );
+    assert_eq!(out, [1, 2, 3]);
+
+    // Length byte right after the tag, the indexes and the total
+    let mut bytes = bytes;
+    let length = HEADER_LEN + 8 + 8 + 2 + 3 + 1 + 8 + 8;
+    assert_eq!(bytes[length], 128);
+    bytes[length] = 200;
+    assert_eq!(decode(&bytes), Err(DecodeError::InvalidFragmentLength(200)));
+}
+
+#[test]
+fn test_codec_errors() {
+    let mut bytes = encode(&fragment_packet(10)).unwrap();
+
+    let mut version = bytes.clone();
+    version[0] = 9;
+    assert_eq!(decode(&version), Err(DecodeError::UnsupportedVersion(9)));
+
+    assert_eq!(
+        frame_len(&[VERSION, 255, 255, 255, 255]),
+        Err(DecodeError::TooLong(u32::MAX as usize + HEADER_LEN))
+    );
+    assert_eq!(frame_len(&[VERSION, 0]), Err(DecodeError::UnexpectedEnd));
+
+    let mut tag = bytes.clone();
+    tag[HEADER_LEN + 8 + 8 + 2 + 3] = 9;
+    assert_eq!(decode(&tag), Err(DecodeError::InvalidTag("packet type", 9)));
+
+    bytes.push(0);
+    assert_eq!(decode(&bytes), Err(DecodeError::TrailingBytes(1)));
+
+    // Body longer than its packet
+    let body_len = bytes.len() - HEADER_LEN;
+    bytes[1] = u8::try_from(body_len).unwrap();
+    assert_eq!(decode(&bytes), Err(DecodeError::TrailingBytes(1)));
+}
+
+#[test]
+fn test_codec_too_long() {
+    let path = vec![(1, NodeType::Drone); MAX_FRAME_LEN / 2];
+    let packet = Packet::new_flood_request(
+        SourceRoutingHeader::new(vec![], 0),
+        1,
+        FloodRequest {
+            flood_id: 1,
+            initiator_id: 1,
+            path_trace: path,
+        },
+    );
+
+    assert!(matches!(encode(&packet), Err(EncodeError::TooLong(_))));
+}
@@ -0,0 +1,283 @@
+use crate::codec;
+use crate::codec::DecodeError;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+
+/// Start of every capture file.
+const MAGIC: &[u8; 5] = b"RDCAP";
+/// Version of the capture format written.
+const VERSION: u8 = 2;
+/// Bytes of a record before its packet: time, drone, direction and neighbor.
+const RECORD_HEADER: usize = 12;
+
+/// Whether a captured packet was received or sent by the drone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Received,
+    Sent,
+}
+
+/// Packet that went through a channel of a drone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedPacket {
+    /// Time since the capture was created.
+    pub at: Duration,
+    pub drone: NodeId,
+    pub direction: Direction,
+    /// Node the packet was received from (if known) or sent to.
+    pub neighbor: Option<NodeId>,
+    pub packet: Packet,
+}
+
+struct CaptureOutput {
+    writer: Box<dyn Write + Send>,
+    error: Option<io::Error>,
+}
+
+/// Tap on the packet channels of the drones, writing every packet to a capture file.
+/// Every drone given a clone writes to the same output.
+/// After the first error nothing else is written, the error is returned by [`Capture::finish`].
+/// A packet that cannot be encoded (a fragment with an invalid length) is an error too.
+///
+/// A capture file starts with `RDCAP` and a version byte. Each record is the time in
+/// nanoseconds (u64), the drone id, the direction (0 received, 1 sent), a flag and the id
+/// of the neighbor, then the packet as a [`codec`] frame. Numbers are little endian.
+#[derive(Clone)]
+pub struct Capture {
+    output: Arc<Mutex<CaptureOutput>>,
+    start: Instant,
+}
+
+impl Capture {
+    /// Capture to `writer`, the time of the packets starts now.
+    #[must_use]
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        let mut output = CaptureOutput {
+            writer: Box::new(writer),
+            error: None,
+        };
+        let header = output
+            .writer
+            .write_all(MAGIC)
+            .and_then(|()| output.writer.write_all(&[VERSION]));
+        output.error = header.err();
+
+        Self {
+            output: Arc::new(Mutex::new(output)),
+            start: Instant::now(),
+        }
+    }
+
+    /// Capture to a new file, or truncate an existing one.
+    /// # Errors
+    /// If the file cannot be created.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Write a packet handled by `drone`, timed from the creation of the capture.
+    /// `neighbor` is the node it was sent to, or received from if known.
+    /// Does nothing once writing failed.
+    pub fn record(
+        &self,
+        drone: NodeId,
+        direction: Direction,
+        neighbor: Option<NodeId>,
+        packet: &Packet,
+    ) {
+        let mut record = Vec::with_capacity(RECORD_HEADER + codec::HEADER_LEN + 64);
+        let at = u64::try_from(self.start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        record.extend_from_slice(&at.to_le_bytes());
+        record.push(drone);
+        record.push(match direction {
+            Direction::Received => 0,
+            Direction::Sent => 1,
+        });
+        record.extend_from_slice(&[u8::from(neighbor.is_some()), neighbor.unwrap_or(0)]);
+        let encoded = codec::encode_into(packet, &mut record);
+
+        let Ok(mut output) = self.output.lock() else {
+            return;
+        };
+        if output.error.is_none() {
+            output.error = match encoded {
+                Ok(()) => output.writer.write_all(&record).err(),
+                Err(err) => Some(io::Error::new(ErrorKind::InvalidInput, err)),
+            };
+        }
+    }
+
+    /// Flush the records written so far.
+    /// # Errors
+    /// The first error met while writing or flushing.
+    pub fn finish(&self) -> io::Result<()> {
+        let mut output = self
+            .output
+            .lock()
+            .map_err(|_| io::Error::other("a drone panicked while capturing"))?;
+        if let Some(err) = output.error.take() {
+            return Err(err);
+        }
+        output.writer.flush()
+    }
+}
+
+/// Node a received packet comes from, according to its routing header or flood path.
+pub(super) fn sender_of(packet: &Packet) -> Option<NodeId> {
+    match packet.pack_type {
+        PacketType::FloodRequest(ref flood) => flood.path_trace.last().map(|(id, _)| *id),
+        _ => packet.routing_header.previous_hop(),
+    }
+}
+
+/// Reason why a capture cannot be read.
+#[derive(Debug)]
+pub enum CaptureError {
+    Io(io::Error),
+    /// The data does not start like a capture file.
+    NotACapture,
+    UnsupportedVersion(u8),
+    /// A record has an invalid direction or neighbor flag.
+    InvalidRecord,
+    /// The packet of a record cannot be decoded.
+    Decode(DecodeError),
+}
+
+impl Display for CaptureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureError::Io(err) => write!(f, "cannot read the capture: {err}"),
+            CaptureError::NotACapture => write!(f, "not a packet capture"),
+            CaptureError::UnsupportedVersion(version) => {
+                write!(f, "unsupported capture version {version}")
+            }
+            CaptureError::InvalidRecord => write!(f, "invalid capture record"),
+            CaptureError::Decode(err) => write!(f, "invalid captured packet: {err}"),
+        }
+    }
+}
+
+impl Error for CaptureError {}
+
+impl From<io::Error> for CaptureError {
+    fn from(err: io::Error) -> Self {
+        CaptureError::Io(err)
+    }
+}
+
+impl From<DecodeError> for CaptureError {
+    fn from(err: DecodeError) -> Self {
+        CaptureError::Decode(err)
+    }
+}
+
+/// Read the records of a capture, in the order they were written.
+pub struct CaptureReader<R> {
+    reader: R,
+    /// Stop after the first error.
+    failed: bool,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// # Errors
+    /// If the header cannot be read or is not the one of a supported capture.
+    pub fn new(mut reader: R) -> Result<Self, CaptureError> {
+        let mut header = [0; MAGIC.len() + 1];
+        let complete = match read_all(&mut reader, &mut header) {
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => false,
+            result => result?,
+        };
+        if !complete || header[..MAGIC.len()] != MAGIC[..] {
+            return Err(CaptureError::NotACapture);
+        }
+        if header[MAGIC.len()] != VERSION {
+            return Err(CaptureError::UnsupportedVersion(header[MAGIC.len()]));
+        }
+        Ok(Self {
+            reader,
+            failed: false,
+        })
+    }
+
+    fn read_record(&mut self) -> Result<Option<CapturedPacket>, CaptureError> {
+        let mut header = [0; RECORD_HEADER];
+        if !read_all(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+
+        let at = u64::from_le_bytes(header[..8].try_into().unwrap_or_default());
+        let direction = match header[9] {
+            0 => Direction::Received,
+            1 => Direction::Sent,
+            _ => return Err(CaptureError::InvalidRecord),
+        };
+        let neighbor = match header[10] {
+            0 => None,
+            1 => Some(header[11]),
+            _ => return Err(CaptureError::InvalidRecord),
+        };
+
+        let mut frame = vec![0; codec::HEADER_LEN];
+        if !read_all(&mut self.reader, &mut frame)? {
+            return Err(CaptureError::Io(ErrorKind::UnexpectedEof.into()));
+        }
+        frame.resize(codec::frame_len(&frame)?, 0);
+        if !read_all(&mut self.reader, &mut frame[codec::HEADER_LEN..])? {
+            return Err(CaptureError::Io(ErrorKind::UnexpectedEof.into()));
+        }
+        let packet = codec::decode(&frame)?;
+
+        Ok(Some(CapturedPacket {
+            at: Duration::from_nanos(at),
+            drone: header[8],
+            direction,
+            neighbor,
+            packet,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CapturedPacket, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let record = self.read_record().transpose();
+        self.failed = matches!(record, Some(Err(_)));
+        record
+    }
+}
+
+/// Read every record of a capture file.
+/// # Errors
+/// If the file cannot be read or is not a valid capture.
+pub fn load_capture(path: impl AsRef<Path>) -> Result<Vec<CapturedPacket>, CaptureError> {
+    CaptureReader::new(BufReader::new(File::open(path)?))?.collect()
+}
+
+/// Fill `buf` from the reader.
+/// # Returns
+/// If it was filled, false if the reader was already at its end.
+fn read_all(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(true)
+}
@@ -1,41 +1,104 @@
-use crate::drone::RustyDrone;
+use crate::drone::stats::increment;
+use crate::drone::{Direction, RustyDrone};
 use wg_2024::controller::DroneEvent::{ControllerShortcut, PacketDropped, PacketSent};
 use wg_2024::network::NodeId;
 use wg_2024::packet::Packet;
 
 impl RustyDrone {
     /// Send packet to the next node in the routing header.
-    pub fn send_to_next(&self, packet: Packet) {
+    /// # Returns
+    /// If the packet was handed to the channel of the next node.
+    pub fn send_to_next(&mut self, packet: Packet) -> bool {
         let Some(next_hop) = packet.routing_header.current_hop() else {
-            return;
+            return false;
         };
 
         let Some(channel) = self.packet_send.get(&next_hop) else {
-            return;
+            return false;
         };
 
-        let _ = channel.send(packet.clone());
+        if channel.send(packet.clone()).is_err() {
+            self.neighbor_unreachable(next_hop);
+            return false;
+        }
+
+        self.count_sent(next_hop, &packet);
+        self.capture_sent(next_hop, &packet);
         let _ = self.controller_send.send(PacketSent(packet));
+        true
+    }
+
+    /// Send a packet created by us to the next node in the routing header,
+    /// using the shortcut if that node cannot be reached.
+    pub(super) fn send_or_shortcut(&mut self, packet: Packet) {
+        if !self.send_to_next(packet.clone()) {
+            self.use_shortcut(packet);
+        }
     }
 
     /// Forward packet to all neighbors except previous hop.
-    pub(super) fn flood_except(&self, previous_hop: NodeId, packet: &Packet) {
+    pub(super) fn flood_except(&mut self, previous_hop: NodeId, packet: &Packet) {
+        let mut unreachable = vec![];
+
         for (node_id, channel) in &self.packet_send {
-            if *node_id != previous_hop {
-                let _ = channel.send(packet.clone());
-                #[cfg(feature = "packet_sent_for_flood")]
+            if *node_id == previous_hop {
+                continue;
+            }
+
+            if channel.send(packet.clone()).is_err() {
+                unreachable.push(*node_id);
+                continue;
+            }
+
+            self.count_sent(*node_id, packet);
+            self.capture_sent(*node_id, packet);
+            if self.packet_sent_for_flood {
                 let _ = self.controller_send.send(PacketSent(packet.clone()));
             }
         }
+
+        for node_id in unreachable {
+            self.neighbor_unreachable(node_id);
+        }
+    }
+
+    /// A neighbor dropped its receiving channel, forget it if configured to.
+    fn neighbor_unreachable(&mut self, node_id: NodeId) {
+        self.stats
+            .update(|stats| increment(&mut stats.send_failures, node_id));
+
+        if self.prune_dead_neighbors {
+            self.packet_send.remove(&node_id);
+        }
     }
 
     /// Send packet over shortcut chanel.
     pub(super) fn use_shortcut(&self, packet: Packet) {
+        self.stats.update(|stats| stats.shortcuts += 1);
         let _ = self.controller_send.send(ControllerShortcut(packet));
     }
 
+    /// Count a packet sent to a neighbor.
+    fn count_sent(&self, neighbor: NodeId, packet: &Packet) {
+        self.stats.update(|stats| {
+            increment(&mut stats.sent, (&packet.pack_type).into());
+            increment(&mut stats.sent_to, neighbor);
+        });
+    }
+
+    /// Write a packet handed to a neighbor to the capture, if any.
+    fn capture_sent(&self, neighbor: NodeId, packet: &Packet) {
+        if let Some(ref capture) = self.capture {
+            capture.record(self.id, Direction::Sent, Some(neighbor), packet);
+        }
+    }
+
     /// Inform Simulation Controller that a packet was dropped.
     pub(super) fn notify_dropped(&self, packet: Packet) {
+        if let Some(next_hop) = packet.routing_header.next_hop() {
+            self.stats
+                .update(|stats| increment(&mut stats.dropped_toward, next_hop));
+        }
         let _ = self.controller_send.send(PacketDropped(packet));
     }
 }
@@ -0,0 +1,344 @@
+use crate::drone::{Bernoulli, Capture, DroneExit, DropModel, NeighborPdr, PacketSpan, RustyDrone};
+use crossbeam_channel::{Receiver, Sender};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Validated set of values needed to create a [`RustyDrone`].
+/// Can only be obtained through [`DroneBuilder::build_config`].
+pub struct DroneConfig {
+    pub(super) id: NodeId,
+    pub(super) controller_send: Sender<DroneEvent>,
+    pub(super) controller_recv: Receiver<DroneCommand>,
+    pub(super) packet_recv: Receiver<Packet>,
+    pub(super) packet_send: HashMap<NodeId, Sender<Packet>>,
+    pub(super) pdr: f32,
+    pub(super) neighbor_pdr: NeighborPdr,
+    pub(super) drop_model: Box<dyn DropModel>,
+    pub(super) exit_send: Option<Sender<DroneExit>>,
+    pub(super) span_send: Option<Sender<PacketSpan>>,
+    pub(super) capture: Option<Capture>,
+    pub(super) options: DroneOptions,
+}
+
+/// Runtime behaviour of the drone that is not part of the standard [`wg_2024::drone::Drone`] interface.
+#[derive(Debug, Clone)]
+pub struct DroneOptions {
+    /// Send a `PacketSent` event to the Simulation Controller for every forwarded flood request.
+    pub packet_sent_for_flood: bool,
+    /// Seed of the random generator used to decide packet drops.
+    pub seed: RngSeed,
+    /// Maximum number of flood requests remembered to detect duplicates.
+    pub flood_cache_capacity: usize,
+    /// Forget flood requests received longer than this ago (never if `None`).
+    pub flood_cache_ttl: Option<Duration>,
+    /// What to do when the Simulation Controller drops its command channel.
+    pub controller_disconnect: ControllerDisconnect,
+    /// How long a crashed drone keeps handling its queued packets.
+    pub drain: DrainPolicy,
+    /// Remove a neighbor as soon as sending to it fails because its channel was dropped.
+    pub prune_dead_neighbors: bool,
+}
+
+impl Default for DroneOptions {
+    fn default() -> Self {
+        Self {
+            packet_sent_for_flood: cfg!(feature = "packet_sent_for_flood"),
+            seed: RngSeed::default(),
+            flood_cache_capacity: 4096,
+            flood_cache_ttl: None,
+            controller_disconnect: ControllerDisconnect::default(),
+            drain: DrainPolicy::default(),
+            prune_dead_neighbors: false,
+        }
+    }
+}
+
+/// Behaviour of the drone once the Simulation Controller is gone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ControllerDisconnect {
+    /// Act as if a `DroneCommand::Crash` was received.
+    #[default]
+    Crash,
+    /// Keep forwarding packets until all the neighbors are gone.
+    ForwardOnly,
+}
+
+/// When a crashed drone stops handling the packets still sent to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DrainPolicy {
+    /// Until every neighbor removed us (as required by the protocol).
+    #[default]
+    UntilDisconnected,
+    /// Until every neighbor removed us, but for at most the given time after the crash.
+    Timeout(Duration),
+    /// Only the packets already queued when crashing.
+    UntilEmpty,
+}
+
+/// Where the drone random generator takes its seed from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RngSeed {
+    /// Non reproducible seed taken from the operating system.
+    #[default]
+    Entropy,
+    /// Use exactly this seed.
+    Fixed(u64),
+    /// Seed shared by the whole simulation, combined with the drone `NodeId`
+    /// so that every drone gets a different (but reproducible) sequence.
+    Simulation(u64),
+}
+
+impl RngSeed {
+    /// Create the random generator for the drone with the given id.
+    pub(super) fn into_rng(self, id: NodeId) -> StdRng {
+        match self {
+            RngSeed::Entropy => StdRng::from_os_rng(),
+            RngSeed::Fixed(seed) => StdRng::seed_from_u64(seed),
+            RngSeed::Simulation(seed) => {
+                StdRng::seed_from_u64(seed ^ u64::from(id).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            }
+        }
+    }
+}
+
+/// Reason why a [`DroneBuilder`] could not produce a [`DroneConfig`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    MissingId,
+    MissingControllerSend,
+    MissingControllerRecv,
+    MissingPacketRecv,
+    /// The packet drop rate is not inside `0.0..=1.0`.
+    InvalidPdr(f32),
+    /// A drop model parameter is not inside `0.0..=1.0`.
+    InvalidProbability(f32),
+    /// The flood cache must be able to hold at least one flood.
+    EmptyFloodCache,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::MissingId => write!(f, "drone id was not set"),
+            ConfigError::MissingControllerSend => write!(f, "controller event channel was not set"),
+            ConfigError::MissingControllerRecv => {
+                write!(f, "controller command channel was not set")
+            }
+            ConfigError::MissingPacketRecv => write!(f, "packet receiving channel was not set"),
+            ConfigError::InvalidPdr(pdr) => write!(f, "packet drop rate {pdr} is not in [0, 1]"),
+            ConfigError::InvalidProbability(p) => write!(f, "probability {p} is not in [0, 1]"),
+            ConfigError::EmptyFloodCache => write!(f, "flood cache capacity must be at least 1"),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Step by step construction of a [`RustyDrone`].
+#[derive(Default)]
+pub struct DroneBuilder {
+    id: Option<NodeId>,
+    controller_send: Option<Sender<DroneEvent>>,
+    controller_recv: Option<Receiver<DroneCommand>>,
+    packet_recv: Option<Receiver<Packet>>,
+    packet_send: HashMap<NodeId, Sender<Packet>>,
+    pdr: f32,
+    neighbor_pdr: NeighborPdr,
+    invalid_pdr: Option<f32>,
+    drop_model: Option<Box<dyn DropModel>>,
+    exit_send: Option<Sender<DroneExit>>,
+    span_send: Option<Sender<PacketSpan>>,
+    capture: Option<Capture>,
+    options: DroneOptions,
+}
+
+impl DroneBuilder {
+    #[must_use]
+    pub fn id(mut self, id: NodeId) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    #[must_use]
+    pub fn controller_send(mut self, controller_send: Sender<DroneEvent>) -> Self {
+        self.controller_send = Some(controller_send);
+        self
+    }
+
+    #[must_use]
+    pub fn controller_recv(mut self, controller_recv: Receiver<DroneCommand>) -> Self {
+        self.controller_recv = Some(controller_recv);
+        self
+    }
+
+    #[must_use]
+    pub fn packet_recv(mut self, packet_recv: Receiver<Packet>) -> Self {
+        self.packet_recv = Some(packet_recv);
+        self
+    }
+
+    /// Replace all the neighbors with the given ones.
+    #[must_use]
+    pub fn packet_send(mut self, packet_send: HashMap<NodeId, Sender<Packet>>) -> Self {
+        self.packet_send = packet_send;
+        self
+    }
+
+    /// Add (or replace) a single neighbor.
+    #[must_use]
+    pub fn neighbor(mut self, node_id: NodeId, sender: Sender<Packet>) -> Self {
+        self.packet_send.insert(node_id, sender);
+        self
+    }
+
+    #[must_use]
+    pub fn pdr(mut self, pdr: f32) -> Self {
+        self.pdr = pdr;
+        self
+    }
+
+    /// Use a different packet drop rate for the link toward `neighbor`.
+    #[must_use]
+    pub fn neighbor_pdr(mut self, neighbor: NodeId, pdr: f32) -> Self {
+        if self.neighbor_pdr.set(neighbor, pdr).is_err() {
+            self.invalid_pdr = Some(pdr);
+        }
+        self
+    }
+
+    /// Share the per link drop rates with an existing handle, to change them at runtime.
+    /// Rates set before with [`DroneBuilder::neighbor_pdr`] are discarded.
+    #[must_use]
+    pub fn neighbor_pdr_handle(mut self, neighbor_pdr: NeighborPdr) -> Self {
+        self.neighbor_pdr = neighbor_pdr;
+        self
+    }
+
+    /// Decide packet drops with the given model instead of [`Bernoulli`].
+    #[must_use]
+    pub fn drop_model(mut self, drop_model: impl DropModel + 'static) -> Self {
+        self.drop_model = Some(Box::new(drop_model));
+        self
+    }
+
+    #[must_use]
+    pub fn packet_sent_for_flood(mut self, enabled: bool) -> Self {
+        self.options.packet_sent_for_flood = enabled;
+        self
+    }
+
+    /// Seed the packet drop generator, making drops reproducible.
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.options.seed = RngSeed::Fixed(seed);
+        self
+    }
+
+    /// Derive the packet drop generator seed from a seed shared by the whole simulation.
+    #[must_use]
+    pub fn simulation_seed(mut self, seed: u64) -> Self {
+        self.options.seed = RngSeed::Simulation(seed);
+        self
+    }
+
+    /// Remember at most `capacity` flood requests, each for at most `ttl` (if any).
+    #[must_use]
+    pub fn flood_cache(mut self, capacity: usize, ttl: Option<Duration>) -> Self {
+        self.options.flood_cache_capacity = capacity;
+        self.options.flood_cache_ttl = ttl;
+        self
+    }
+
+    #[must_use]
+    pub fn controller_disconnect(mut self, policy: ControllerDisconnect) -> Self {
+        self.options.controller_disconnect = policy;
+        self
+    }
+
+    #[must_use]
+    pub fn drain(mut self, policy: DrainPolicy) -> Self {
+        self.options.drain = policy;
+        self
+    }
+
+    #[must_use]
+    pub fn prune_dead_neighbors(mut self, enabled: bool) -> Self {
+        self.options.prune_dead_neighbors = enabled;
+        self
+    }
+
+    /// Receive a [`DroneExit`] when the drone stops running.
+    #[must_use]
+    pub fn exit_send(mut self, exit_send: Sender<DroneExit>) -> Self {
+        self.exit_send = Some(exit_send);
+        self
+    }
+
+    /// Receive a [`PacketSpan`] after every packet handled.
+    #[must_use]
+    pub fn span_send(mut self, span_send: Sender<PacketSpan>) -> Self {
+        self.span_send = Some(span_send);
+        self
+    }
+
+    /// Write every packet received and sent to the capture.
+    #[must_use]
+    pub fn capture(mut self, capture: Capture) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    #[must_use]
+    pub fn options(mut self, options: DroneOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Check that every required value is present and valid.
+    /// # Errors
+    /// The first missing or invalid value found.
+    pub fn build_config(self) -> Result<DroneConfig, ConfigError> {
+        if !(0.0..=1.0).contains(&self.pdr) {
+            return Err(ConfigError::InvalidPdr(self.pdr));
+        }
+        if let Some(pdr) = self.invalid_pdr {
+            return Err(ConfigError::InvalidPdr(pdr));
+        }
+        if self.options.flood_cache_capacity == 0 {
+            return Err(ConfigError::EmptyFloodCache);
+        }
+
+        Ok(DroneConfig {
+            id: self.id.ok_or(ConfigError::MissingId)?,
+            controller_send: self
+                .controller_send
+                .ok_or(ConfigError::MissingControllerSend)?,
+            controller_recv: self
+                .controller_recv
+                .ok_or(ConfigError::MissingControllerRecv)?,
+            packet_recv: self.packet_recv.ok_or(ConfigError::MissingPacketRecv)?,
+            packet_send: self.packet_send,
+            pdr: self.pdr,
+            neighbor_pdr: self.neighbor_pdr,
+            drop_model: self.drop_model.unwrap_or_else(|| Box::new(Bernoulli)),
+            exit_send: self.exit_send,
+            span_send: self.span_send,
+            capture: self.capture,
+            options: self.options,
+        })
+    }
+
+    /// Validate the configuration and create the drone.
+    /// # Errors
+    /// See [`DroneBuilder::build_config`].
+    pub fn build(self) -> Result<RustyDrone, ConfigError> {
+        self.build_config().map(RustyDrone::from_config)
+    }
+}
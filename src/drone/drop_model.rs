@@ -0,0 +1,150 @@
+use crate::drone::ConfigError;
+use rand::{Rng, RngCore};
+use std::fs;
+use std::io;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Decides, one droppable packet at a time, if the packet is lost.
+pub trait DropModel: Send {
+    /// Return if the next droppable packet should be dropped.
+    /// `pdr` is the packet drop rate currently set on the drone
+    /// (the one changed by `DroneCommand::SetPacketDropRate`).
+    fn should_drop(&mut self, pdr: f32, rng: &mut dyn RngCore) -> bool;
+}
+
+/// Independent uniform trial against the packet drop rate of the drone.
+/// This is the behaviour required by the protocol and the default model.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bernoulli;
+
+impl DropModel for Bernoulli {
+    fn should_drop(&mut self, pdr: f32, rng: &mut dyn RngCore) -> bool {
+        rng.random_range(0.0..1.0) < pdr
+    }
+}
+
+/// Two state (good and bad) Markov chain producing bursty losses.
+/// The packet drop rate of the drone is ignored.
+#[derive(Debug, Clone)]
+pub struct GilbertElliott {
+    /// Probability of moving from the good to the bad state after a packet.
+    good_to_bad: f32,
+    /// Probability of moving from the bad to the good state after a packet.
+    bad_to_good: f32,
+    /// Probability of losing a packet while in the good state.
+    loss_good: f32,
+    /// Probability of losing a packet while in the bad state.
+    loss_bad: f32,
+    /// If the channel is currently in the bad state.
+    bad: bool,
+}
+
+impl GilbertElliott {
+    /// Create the model, starting in the good state.
+    /// # Errors
+    /// If any of the values is not a probability.
+    pub fn new(
+        good_to_bad: f32,
+        bad_to_good: f32,
+        loss_good: f32,
+        loss_bad: f32,
+    ) -> Result<Self, ConfigError> {
+        for p in [good_to_bad, bad_to_good, loss_good, loss_bad] {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(ConfigError::InvalidProbability(p));
+            }
+        }
+
+        Ok(Self {
+            good_to_bad,
+            bad_to_good,
+            loss_good,
+            loss_bad,
+            bad: false,
+        })
+    }
+}
+
+impl DropModel for GilbertElliott {
+    fn should_drop(&mut self, _pdr: f32, rng: &mut dyn RngCore) -> bool {
+        let loss = if self.bad {
+            self.loss_bad
+        } else {
+            self.loss_good
+        };
+        let dropped = rng.random_range(0.0..1.0) < loss;
+
+        let change = if self.bad {
+            self.bad_to_good
+        } else {
+            self.good_to_bad
+        };
+        if rng.random_range(0.0..1.0) < change {
+            self.bad = !self.bad;
+        }
+
+        dropped
+    }
+}
+
+/// Replay a recorded sequence of drop decisions, restarting from the beginning when it ends.
+/// The packet drop rate of the drone is ignored.
+#[derive(Debug, Clone)]
+pub struct TraceDriven {
+    trace: Vec<bool>,
+    position: usize,
+}
+
+impl TraceDriven {
+    /// Use the given decisions (`true` means dropped). An empty trace never drops.
+    #[must_use]
+    pub fn new(trace: Vec<bool>) -> Self {
+        Self { trace, position: 0 }
+    }
+
+    /// Load a trace file: `0` (delivered) and `1` (dropped) separated by whitespace or commas.
+    /// Everything after a `#` on a line is a comment.
+    /// # Errors
+    /// If the file cannot be read or contains something other than `0` and `1`.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse a trace in the format of [`TraceDriven::from_file`].
+    /// # Errors
+    /// If the text contains something other than `0` and `1`.
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut trace = vec![];
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            for token in line.split(|c: char| c.is_whitespace() || c == ',') {
+                match token {
+                    "" => {}
+                    "0" => trace.push(false),
+                    "1" => trace.push(true),
+                    _ => {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("invalid drop trace value `{token}`"),
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(Self::new(trace))
+    }
+}
+
+impl DropModel for TraceDriven {
+    fn should_drop(&mut self, _pdr: f32, _rng: &mut dyn RngCore) -> bool {
+        let Some(&dropped) = self.trace.get(self.position) else {
+            return false;
+        };
+
+        self.position = (self.position + 1) % self.trace.len();
+        dropped
+    }
+}
@@ -0,0 +1,17 @@
+use wg_2024::network::NodeId;
+
+/// Sent by a drone right before its `run` returns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroneExit {
+    pub id: NodeId,
+    pub reason: ExitReason,
+}
+
+/// Why a drone stopped running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// The drone crashed and finished draining its queued packets.
+    Crashed,
+    /// Both the Simulation Controller and all the neighbors are gone.
+    Disconnected,
+}
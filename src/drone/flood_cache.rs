@@ -0,0 +1,67 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+
+/// Flood requests `(flood_id, initiator_id)` received recently.
+/// Holds at most `capacity` entries (the oldest are forgotten first),
+/// and entries older than `ttl` are forgotten.
+pub(super) struct FloodCache {
+    capacity: usize,
+    ttl: Option<Duration>,
+    /// When each remembered flood was first received.
+    received: HashMap<(u64, NodeId), Instant>,
+    /// Remembered floods from the oldest to the newest.
+    order: VecDeque<((u64, NodeId), Instant)>,
+}
+
+impl FloodCache {
+    pub(super) fn new(capacity: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            capacity,
+            ttl,
+            received: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Remember the flood, returns if it was already remembered.
+    pub(super) fn insert(&mut self, flood: (u64, NodeId), now: Instant) -> bool {
+        self.forget_expired(now);
+
+        if self.received.contains_key(&flood) {
+            return true;
+        }
+
+        self.received.insert(flood, now);
+        self.order.push_back((flood, now));
+
+        while self.received.len() > self.capacity {
+            self.forget_oldest();
+        }
+        false
+    }
+
+    /// Number of floods remembered.
+    pub(super) fn len(&self) -> usize {
+        self.received.len()
+    }
+
+    fn forget_expired(&mut self, now: Instant) {
+        let Some(ttl) = self.ttl else {
+            return;
+        };
+
+        while let Some((_, received)) = self.order.front() {
+            if now.saturating_duration_since(*received) < ttl {
+                break;
+            }
+            self.forget_oldest();
+        }
+    }
+
+    fn forget_oldest(&mut self) {
+        if let Some((flood, _)) = self.order.pop_front() {
+            self.received.remove(&flood);
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use crate::drone::RustyDrone;
+use crate::drone::stats::increment;
+use crate::drone::{PacketKind, RustyDrone};
 use wg_2024::network::SourceRoutingHeader;
 use wg_2024::packet::{FloodRequest, FloodResponse, NodeType, Packet};
 
@@ -6,8 +7,20 @@ impl RustyDrone {
     /// Handle the processing of flood-request packets.
     pub(super) fn respond_flood_request(&mut self, session_id: u64, flood: &FloodRequest) {
         let no_other_neighbours = self.packet_send.len() == 1;
+        let terminate = self.already_received_flood(flood) || no_other_neighbours;
 
-        if self.already_received_flood(flood) || no_other_neighbours {
+        let flood_cache_size = self.received_floods.len();
+        self.stats.update(|stats| {
+            increment(&mut stats.received, PacketKind::FloodRequest);
+            if terminate {
+                stats.floods_terminated += 1;
+            } else {
+                stats.floods_forwarded += 1;
+            }
+            stats.flood_cache_size = flood_cache_size;
+        });
+
+        if terminate {
             // Flood request is already seen or we have no one to forward it to, it should be terminated.
             self.respond_old(session_id, flood);
         } else {
@@ -17,7 +30,7 @@ impl RustyDrone {
     }
 
     /// Handle flood request termination, sending back a flood response.
-    fn respond_old(&self, session_id: u64, request: &FloodRequest) {
+    fn respond_old(&mut self, session_id: u64, request: &FloodRequest) {
         let mut new_path = request.path_trace.clone();
         // Add ourselves to the path
         new_path.push((self.id, NodeType::Drone));
@@ -36,7 +49,7 @@ impl RustyDrone {
         }
 
         // Send back flood response
-        self.send_to_next(Packet::new_flood_response(
+        self.send_or_shortcut(Packet::new_flood_response(
             SourceRoutingHeader { hop_index: 1, hops },
             session_id,
             FloodResponse {
@@ -48,7 +61,7 @@ impl RustyDrone {
 
     /// Flood request has not finished yet, forward it to all our neighbors
     /// (excluding the one that send it to us).
-    fn respond_new(&self, session_id: u64, flood: &FloodRequest) {
+    fn respond_new(&mut self, session_id: u64, flood: &FloodRequest) {
         // Exclude the neighbor we received the packet from in the forward
         // Fall back on initiator id in case path_trace is empty
         let prev_hop = flood.path_trace.last().map_or(flood.initiator_id, |x| x.0);
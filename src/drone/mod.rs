@@ -1,12 +1,35 @@
+mod capture;
 mod channels;
 mod command_handler;
+mod config;
+mod drop_model;
+mod exit;
+mod flood_cache;
 mod flood_handler;
+mod neighbor_pdr;
 mod packet_handler;
+mod span;
+mod stats;
 mod test;
 mod utils;
 
-use crossbeam_channel::{select_biased, Receiver, Sender};
-use std::collections::{HashMap, HashSet};
+pub use capture::{load_capture, Capture, CaptureError, CaptureReader, CapturedPacket, Direction};
+pub use config::{
+    ConfigError, ControllerDisconnect, DrainPolicy, DroneBuilder, DroneConfig, DroneOptions,
+    RngSeed,
+};
+pub use drop_model::{Bernoulli, DropModel, GilbertElliott, TraceDriven};
+pub use exit::{DroneExit, ExitReason};
+pub use neighbor_pdr::NeighborPdr;
+pub use span::PacketSpan;
+pub use stats::{DroneStats, DroneStatsHandle, MalformedRouting, NackKind, PacketKind};
+
+use crossbeam_channel::{never, select_biased, Receiver, Sender};
+use flood_cache::FloodCache;
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
 use wg_2024::controller::{DroneCommand, DroneEvent};
 use wg_2024::drone::Drone;
 use wg_2024::network::NodeId;
@@ -22,10 +45,33 @@ pub struct RustyDrone {
     packet_recv: Receiver<Packet>,
     /// Per (connected neighbor) `NodeId`, what channel to use to send packets to it.
     packet_send: HashMap<NodeId, Sender<Packet>>,
+    /// Per (connected neighbor) `NodeId`, the packet drop rate of the link toward it.
+    /// Links without an entry use `pdr`.
+    neighbor_pdr: NeighborPdr,
     /// Packet Drop Rate.
     pdr: f32,
-    /// Store all flood requests that have been received at least once.
-    received_floods: HashSet<(u64, NodeId)>,
+    /// Random generator used to decide which packets are dropped.
+    rng: StdRng,
+    /// How packet drops are decided.
+    drop_model: Box<dyn DropModel>,
+    /// Store the flood requests that have been received at least once (recently).
+    received_floods: FloodCache,
+    /// Send a `PacketSent` event also for forwarded flood requests.
+    packet_sent_for_flood: bool,
+    /// What to do once the Simulation Controller is gone.
+    controller_disconnect: ControllerDisconnect,
+    /// How long to keep handling packets after crashing.
+    drain: DrainPolicy,
+    /// Remove neighbors whose channel was dropped.
+    prune_dead_neighbors: bool,
+    /// Notified when we stop running.
+    exit_send: Option<Sender<DroneExit>>,
+    /// Notified of the time spent handling every packet.
+    span_send: Option<Sender<PacketSpan>>,
+    /// Tap on the packets received and sent.
+    capture: Option<Capture>,
+    /// Counters readable from other threads.
+    stats: Arc<DroneStatsHandle>,
 }
 
 impl Drone for RustyDrone {
@@ -37,47 +83,193 @@ impl Drone for RustyDrone {
         packet_send: HashMap<NodeId, Sender<Packet>>,
         pdr: f32,
     ) -> Self {
-        Self {
+        Self::from_config(DroneConfig {
             id,
             controller_send,
             controller_recv,
             packet_recv,
-            pdr,
             packet_send,
-            received_floods: HashSet::new(),
-        }
+            pdr,
+            neighbor_pdr: NeighborPdr::default(),
+            drop_model: Box::new(Bernoulli),
+            exit_send: None,
+            span_send: None,
+            capture: None,
+            options: DroneOptions::default(),
+        })
     }
 
     /// Continuously process messages (blocking) until we crash.
     fn run(&mut self) {
+        let reason = if self.run_until_crash() {
+            self.drain_crashed();
+            ExitReason::Crashed
+        } else {
+            ExitReason::Disconnected
+        };
+
+        if let Some(ref exit_send) = self.exit_send {
+            let _ = exit_send.send(DroneExit {
+                id: self.id,
+                reason,
+            });
+        }
+    }
+}
+
+impl RustyDrone {
+    /// Process messages (blocking) until we crash.
+    /// # Returns
+    /// If we crashed, otherwise every channel got disconnected.
+    fn run_until_crash(&mut self) -> bool {
+        // Disconnected channels are replaced with one that never receives
+        let mut controller_recv = self.controller_recv.clone();
+        let mut packet_recv = self.packet_recv.clone();
+        let mut controller_connected = true;
+        let mut packet_connected = true;
+
         let mut crashing = false;
         while !crashing {
             // Repeatedly try to read a message from either
             // the Simulation Controller (priority) or one of our neighbor nodes
             select_biased! {
-                recv(self.controller_recv) -> res => {
-                    if let Ok(ref packet) = res{
-                        crashing = self.handle_commands(packet);
+                recv(controller_recv) -> res => {
+                    if let Ok(ref command) = res {
+                        crashing = self.handle_commands(command);
+                    } else {
+                        controller_connected = false;
+                        controller_recv = never();
+                        crashing = self.controller_disconnect == ControllerDisconnect::Crash;
                     }
                 },
-                recv(self.packet_recv) -> res => {
-                    if let Ok(ref packet) = res{
+                recv(packet_recv) -> res => {
+                    if let Ok(ref packet) = res {
                         self.handle_packet(packet, false);
+                    } else {
+                        packet_connected = false;
+                        packet_recv = never();
                     }
                 },
             }
+
+            if !controller_connected && !packet_connected {
+                // Nobody can reach us anymore
+                return false;
+            }
         }
+        true
+    }
+
+    /// Handle remaining queued packets as crashed drone, as long as the drain policy allows.
+    fn drain_crashed(&mut self) {
+        let deadline = match self.drain {
+            DrainPolicy::Timeout(timeout) => Some(Instant::now() + timeout),
+            _ => None,
+        };
 
-        // Handle remaining queued packets as crashed drone
-        while let Ok(ref packet) = self.packet_recv.recv() {
+        loop {
+            let res = match (self.drain, deadline) {
+                (DrainPolicy::UntilEmpty, _) => self.packet_recv.try_recv().ok(),
+                (_, Some(deadline)) => self.packet_recv.recv_deadline(deadline).ok(),
+                (_, None) => self.packet_recv.recv().ok(),
+            };
+
+            let Some(ref packet) = res else {
+                return;
+            };
             self.handle_packet(packet, true);
         }
     }
-}
 
-impl RustyDrone {
-    /// Forward the packet to the respective handler function.
+    /// Start building a drone with non-default options.
+    #[must_use]
+    pub fn builder() -> DroneBuilder {
+        DroneBuilder::default()
+    }
+
+    /// Create the drone from an already validated configuration.
+    #[must_use]
+    pub fn from_config(config: DroneConfig) -> Self {
+        Self {
+            id: config.id,
+            controller_send: config.controller_send,
+            controller_recv: config.controller_recv,
+            packet_recv: config.packet_recv,
+            packet_send: config.packet_send,
+            neighbor_pdr: config.neighbor_pdr,
+            pdr: config.pdr,
+            rng: config.options.seed.into_rng(config.id),
+            drop_model: config.drop_model,
+            received_floods: FloodCache::new(
+                config.options.flood_cache_capacity,
+                config.options.flood_cache_ttl,
+            ),
+            packet_sent_for_flood: config.options.packet_sent_for_flood,
+            controller_disconnect: config.options.controller_disconnect,
+            drain: config.options.drain,
+            prune_dead_neighbors: config.options.prune_dead_neighbors,
+            exit_send: config.exit_send,
+            span_send: config.span_send,
+            capture: config.capture,
+            stats: Arc::default(),
+        }
+    }
+
+    /// Handle to change the per link packet drop rates while the drone is running.
+    #[must_use]
+    pub fn neighbor_pdr(&self) -> NeighborPdr {
+        self.neighbor_pdr.clone()
+    }
+
+    /// Number of flood requests currently remembered to detect duplicates.
+    #[must_use]
+    pub fn flood_cache_len(&self) -> usize {
+        self.received_floods.len()
+    }
+
+    /// Handle to read the drone counters while it is running.
+    #[must_use]
+    pub fn stats(&self) -> Arc<DroneStatsHandle> {
+        self.stats.clone()
+    }
+
+    /// Handle a single packet as if it was received by `run`.
+    #[cfg(feature = "fuzzing")]
+    pub fn fuzz_handle_packet(&mut self, packet: &Packet, crashing: bool) {
+        self.handle_packet(packet, crashing);
+    }
+
+    /// Handle a single command as if it was received by `run`, returns if the drone crashed.
+    #[cfg(feature = "fuzzing")]
+    pub fn fuzz_handle_commands(&mut self, command: &DroneCommand) -> bool {
+        self.handle_commands(command)
+    }
+
+    /// Handle the packet, timing it if a span channel was given.
     fn handle_packet(&mut self, packet: &Packet, crashing: bool) {
+        if let Some(ref capture) = self.capture {
+            let sender = capture::sender_of(packet);
+            capture.record(self.id, Direction::Received, sender, packet);
+        }
+
+        let Some(span_send) = self.span_send.clone() else {
+            self.dispatch_packet(packet, crashing);
+            return;
+        };
+
+        let start = Instant::now();
+        self.dispatch_packet(packet, crashing);
+        let _ = span_send.send(PacketSpan {
+            id: self.id,
+            packet: packet.clone(),
+            start,
+            duration: start.elapsed(),
+            crashing,
+        });
+    }
+
+    /// Forward the packet to the respective handler function.
+    fn dispatch_packet(&mut self, packet: &Packet, crashing: bool) {
         if let PacketType::FloodRequest(ref flood) = packet.pack_type {
             if !crashing {
                 self.respond_flood_request(packet.session_id, flood);
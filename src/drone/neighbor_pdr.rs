@@ -0,0 +1,47 @@
+use crate::drone::ConfigError;
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+use wg_2024::network::NodeId;
+
+/// Packet drop rate of single links, keyed by next hop.
+/// Cloning gives another handle to the same rates, so they can be changed
+/// while the drone is running on its own thread.
+#[derive(Debug, Clone, Default)]
+pub struct NeighborPdr {
+    rates: Arc<RwLock<HashMap<NodeId, f32>>>,
+}
+
+impl NeighborPdr {
+    /// Set the drop rate used for packets sent to `neighbor`.
+    /// # Errors
+    /// If `pdr` is not inside `0.0..=1.0`.
+    pub fn set(&self, neighbor: NodeId, pdr: f32) -> Result<(), ConfigError> {
+        if !(0.0..=1.0).contains(&pdr) {
+            return Err(ConfigError::InvalidPdr(pdr));
+        }
+
+        self.rates
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(neighbor, pdr);
+        Ok(())
+    }
+
+    /// Go back to the drone packet drop rate for packets sent to `neighbor`.
+    pub fn remove(&self, neighbor: NodeId) {
+        self.rates
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&neighbor);
+    }
+
+    /// Drop rate of the link toward `neighbor`, if it has its own.
+    #[must_use]
+    pub fn get(&self, neighbor: NodeId) -> Option<f32> {
+        self.rates
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&neighbor)
+            .copied()
+    }
+}
@@ -1,12 +1,22 @@
-use crate::drone::{utils, RustyDrone};
+use crate::drone::stats::increment;
+use crate::drone::{utils, MalformedRouting, RustyDrone};
+use wg_2024::controller::DroneEvent::PacketDropped;
 use wg_2024::packet::NackType::{DestinationIsDrone, Dropped, ErrorInRouting, UnexpectedRecipient};
 use wg_2024::packet::{Nack, NackType, Packet, PacketType};
 
 impl RustyDrone {
     /// Handle the processing of non-flood-request packets.
-    pub(super) fn respond_normal(&self, packet: &Packet, crashing: bool) {
+    pub(super) fn respond_normal(&mut self, packet: &Packet, crashing: bool) {
         let droppable = matches!(packet.pack_type, PacketType::MsgFragment(_));
         let routing = &packet.routing_header;
+        self.stats
+            .update(|stats| increment(&mut stats.received, (&packet.pack_type).into()));
+
+        // The routing header cannot be followed, not even to send a nack back
+        if let Err(malformed) = utils::validate_routing(routing) {
+            self.discard_malformed(packet, malformed, droppable);
+            return;
+        }
 
         // We received this packet, but according to the routing header, we are not the current node on the path
         if routing.current_hop() != Some(self.id) {
@@ -20,7 +30,7 @@ impl RustyDrone {
             return;
         }
 
-        match routing.next_hop() {
+        let next = match routing.next_hop() {
             None => {
                 // This packet was send to us, but drones are not valid end destinations
                 if droppable {
@@ -34,10 +44,11 @@ impl RustyDrone {
                     self.nack_packet(packet, ErrorInRouting(next), droppable, true);
                     return;
                 }
+                next
             }
-        }
+        };
 
-        if droppable && self.should_drop() {
+        if droppable && self.should_drop(next) {
             // Packet got dropped by packet drop rate
             self.notify_dropped(packet.clone());
             self.nack_packet(packet, Dropped, droppable, false);
@@ -45,11 +56,16 @@ impl RustyDrone {
         }
 
         // Forward packet to the next node in the route (one of our neighbors)
-        self.forward_packet(packet);
+        if !self.forward_packet(packet) {
+            // The next node is gone even if we still had its channel
+            self.nack_packet(packet, ErrorInRouting(next), droppable, true);
+        }
     }
 
     /// Send packet to the next node in the packet route.
-    fn forward_packet(&self, packet: &Packet) {
+    /// # Returns
+    /// If the packet was handed to the next node.
+    fn forward_packet(&mut self, packet: &Packet) -> bool {
         let mut routing_header = packet.routing_header.clone();
 
         // Set the current hop of the route to the next node
@@ -59,12 +75,27 @@ impl RustyDrone {
             routing_header,
             session_id: packet.session_id,
             pack_type: packet.pack_type.clone(),
-        });
+        })
+    }
+
+    /// Handle a packet whose routing header cannot be followed.
+    /// Packets that cannot be dropped are given to the Simulation Controller unchanged,
+    /// fragments are dropped without a nack, as there is no route to send it back.
+    fn discard_malformed(&self, packet: &Packet, malformed: MalformedRouting, droppable: bool) {
+        self.stats
+            .update(|stats| increment(&mut stats.malformed, malformed));
+
+        if droppable {
+            // Not counted as dropped toward a neighbor, the route has none to blame
+            let _ = self.controller_send.send(PacketDropped(packet.clone()));
+        } else if malformed != MalformedRouting::EmptyRoute {
+            self.use_shortcut(packet.clone());
+        }
     }
 
     /// Send nack in response to received packet.
     fn nack_packet(
-        &self,
+        &mut self,
         packet: &Packet,
         nack_type: NackType,
         droppable: bool,
@@ -85,8 +116,11 @@ impl RustyDrone {
             return;
         }
 
+        self.stats
+            .update(|stats| increment(&mut stats.nacks, (&nack_type).into()));
+
         // Send nack to the first hop
-        self.send_to_next(Packet::new_nack(
+        self.send_or_shortcut(Packet::new_nack(
             self.get_routing_back(&packet.routing_header),
             packet.session_id,
             Nack {
@@ -0,0 +1,16 @@
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Time spent by a drone handling a received packet.
+/// Sent after every packet when a span channel was given to the builder.
+#[derive(Debug, Clone)]
+pub struct PacketSpan {
+    pub id: NodeId,
+    /// The packet as it was received.
+    pub packet: Packet,
+    pub start: Instant,
+    pub duration: Duration,
+    /// If the packet was handled after the drone crashed.
+    pub crashing: bool,
+}
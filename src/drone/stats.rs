@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Mutex, PoisonError};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, PacketType};
+
+/// Type of packet, without its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketKind {
+    MsgFragment,
+    Ack,
+    Nack,
+    FloodRequest,
+    FloodResponse,
+}
+
+impl From<&PacketType> for PacketKind {
+    fn from(packet_type: &PacketType) -> Self {
+        match packet_type {
+            PacketType::MsgFragment(_) => PacketKind::MsgFragment,
+            PacketType::Ack(_) => PacketKind::Ack,
+            PacketType::Nack(_) => PacketKind::Nack,
+            PacketType::FloodRequest(_) => PacketKind::FloodRequest,
+            PacketType::FloodResponse(_) => PacketKind::FloodResponse,
+        }
+    }
+}
+
+/// Type of nack, without the node it refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum NackKind {
+    ErrorInRouting,
+    DestinationIsDrone,
+    Dropped,
+    UnexpectedRecipient,
+}
+
+impl From<&NackType> for NackKind {
+    fn from(nack_type: &NackType) -> Self {
+        match nack_type {
+            NackType::ErrorInRouting(_) => NackKind::ErrorInRouting,
+            NackType::DestinationIsDrone => NackKind::DestinationIsDrone,
+            NackType::Dropped => NackKind::Dropped,
+            NackType::UnexpectedRecipient(_) => NackKind::UnexpectedRecipient,
+        }
+    }
+}
+
+/// Reason why a routing header cannot be followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MalformedRouting {
+    /// There are no hops at all.
+    EmptyRoute,
+    /// `hop_index` is 0, so nobody could have sent the packet to us.
+    NoPreviousHop,
+    /// `hop_index` points past the last hop.
+    HopIndexOutOfRange,
+}
+
+/// Counters of everything a drone did since it was created.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DroneStats {
+    /// Packets received, per type.
+    pub received: HashMap<PacketKind, u64>,
+    /// Packets sent to a neighbor, per type.
+    pub sent: HashMap<PacketKind, u64>,
+    /// Packets sent, per neighbor.
+    pub sent_to: HashMap<NodeId, u64>,
+    /// Fragments dropped because of the packet drop rate, per neighbor they were meant for.
+    pub dropped_toward: HashMap<NodeId, u64>,
+    /// Nacks created by this drone, per type.
+    pub nacks: HashMap<NackKind, u64>,
+    /// Sends that failed because the neighbor dropped its channel, per neighbor.
+    pub send_failures: HashMap<NodeId, u64>,
+    /// Packets with a routing header that cannot be followed, per reason.
+    pub malformed: HashMap<MalformedRouting, u64>,
+    /// Packets sent through the Simulation Controller shortcut.
+    pub shortcuts: u64,
+    /// Flood requests forwarded to the neighbors.
+    pub floods_forwarded: u64,
+    /// Flood requests terminated with a flood response.
+    pub floods_terminated: u64,
+    /// Flood requests currently remembered to detect duplicates.
+    pub flood_cache_size: usize,
+}
+
+impl DroneStats {
+    /// Total number of fragments dropped because of the packet drop rate.
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.dropped_toward.values().sum()
+    }
+}
+
+/// Shared access to the [`DroneStats`] of a drone running on another thread.
+#[derive(Debug, Default)]
+pub struct DroneStatsHandle {
+    stats: Mutex<DroneStats>,
+}
+
+impl DroneStatsHandle {
+    /// Copy of the current counters.
+    #[must_use]
+    pub fn snapshot(&self) -> DroneStats {
+        self.stats
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub(super) fn update(&self, update: impl FnOnce(&mut DroneStats)) {
+        update(&mut self.stats.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+}
+
+/// Add one to the counter of `key`.
+pub(super) fn increment<K: Eq + Hash>(counters: &mut HashMap<K, u64>, key: K) {
+    *counters.entry(key).or_default() += 1;
+}
@@ -0,0 +1,160 @@
+#![cfg(test)]
+
+use crate::codec::DecodeError;
+use crate::drone::{
+    load_capture, Capture, CaptureError, CaptureReader, CapturedPacket, Direction, RustyDrone,
+};
+use crossbeam_channel::{unbounded, Receiver};
+use rusty_tester::utils::data::{new_flood_request, new_forwarded, new_test_fragment_packet};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NodeType, Packet, PacketType};
+
+fn capture_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rusty_{name}_{}.cap", std::process::id()))
+}
+
+/// Drone 11 linked to 1 and 12, with the receiver of 12.
+fn captured_drone(capture: Capture) -> (RustyDrone, Receiver<Packet>) {
+    let (neighbor_send, neighbor_recv) = unbounded();
+    let drone = RustyDrone::builder()
+        .id(11)
+        .controller_send(unbounded().0)
+        .controller_recv(unbounded().1)
+        .packet_recv(unbounded().1)
+        .neighbor(1, unbounded().0)
+        .neighbor(12, neighbor_send)
+        .capture(capture)
+        .build()
+        .unwrap();
+    (drone, neighbor_recv)
+}
+
+fn summary(captured: &[CapturedPacket]) -> Vec<(NodeId, Direction, Option<NodeId>, Packet)> {
+    captured
+        .iter()
+        .map(|c| (c.drone, c.direction, c.neighbor, c.packet.clone()))
+        .collect()
+}
+
+#[test]
+fn test_drone_capture_packets() {
+    let path = capture_path("capture");
+    let capture = Capture::create(&path).unwrap();
+    let (mut drone, _neighbor) = captured_drone(capture.clone());
+
+    let fragment = new_test_fragment_packet(&[1, 11, 12], 5);
+    let flood = new_flood_request(6, 3, 1, true);
+    drone.handle_packet(&fragment, false);
+    drone.handle_packet(&flood, false);
+    capture.finish().unwrap();
+    let captured = load_capture(&path);
+    std::fs::remove_file(&path).unwrap();
+    let captured = captured.unwrap();
+
+    let mut forwarded_flood = flood.clone();
+    if let PacketType::FloodRequest(ref mut request) = forwarded_flood.pack_type {
+        request.path_trace.push((11, NodeType::Drone));
+    }
+    assert_eq!(
+        summary(&captured),
+        vec![
+            (11, Direction::Received, Some(1), fragment.clone()),
+            (11, Direction::Sent, Some(12), new_forwarded(&fragment)),
+            (11, Direction::Received, Some(1), flood),
+            (11, Direction::Sent, Some(12), forwarded_flood),
+        ]
+    );
+    assert!(captured.windows(2).all(|c| c[0].at <= c[1].at));
+}
+
+#[test]
+fn test_drone_capture_reinject() {
+    let path = capture_path("reinject");
+    let capture = Capture::create(&path).unwrap();
+    let (mut drone, neighbor) = captured_drone(capture.clone());
+    for session in 0..3 {
+        drone.handle_packet(&new_test_fragment_packet(&[1, 11, 12], session), false);
+    }
+    capture.finish().unwrap();
+    let captured = load_capture(&path);
+    std::fs::remove_file(&path).unwrap();
+    let captured = captured.unwrap();
+
+    // The received packets replayed on a new drone are sent out in the same way
+    let (mut replayed, replayed_neighbor) = captured_drone(Capture::new(std::io::sink()));
+    for record in captured
+        .iter()
+        .filter(|c| c.direction == Direction::Received)
+    {
+        replayed.handle_packet(&record.packet, false);
+    }
+    let sent = captured
+        .iter()
+        .filter(|c| c.direction == Direction::Sent)
+        .map(|c| c.packet.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(neighbor.try_iter().collect::<Vec<_>>(), sent);
+    assert_eq!(replayed_neighbor.try_iter().collect::<Vec<_>>(), sent);
+}
+
+#[test]
+fn test_drone_capture_encode_error() {
+    let capture = Capture::new(std::io::sink());
+    let mut fragment = new_test_fragment_packet(&[1, 11, 12], 5);
+    capture.record(11, Direction::Received, Some(1), &fragment);
+    capture.finish().unwrap();
+
+    if let PacketType::MsgFragment(ref mut data) = fragment.pack_type {
+        data.length = 200;
+    }
+    capture.record(11, Direction::Received, Some(1), &fragment);
+    let err = capture.finish().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_drone_capture_reader_errors() {
+    assert!(matches!(
+        CaptureReader::new(&b"PCAP\x01"[..]),
+        Err(CaptureError::NotACapture)
+    ));
+    assert!(matches!(
+        CaptureReader::new(&b"RDCAP\x09"[..]),
+        Err(CaptureError::UnsupportedVersion(9))
+    ));
+    assert!(matches!(
+        CaptureReader::new(&b"RDCAP\x01"[..]),
+        Err(CaptureError::UnsupportedVersion(1))
+    ));
+    assert_eq!(CaptureReader::new(&b"RDCAP\x02"[..]).unwrap().count(), 0);
+
+    // Frame announcing a longer packet than the data
+    let mut truncated = b"RDCAP\x02".to_vec();
+    truncated.extend_from_slice(&[0; 8]);
+    truncated.extend_from_slice(&[11, 0, 1, 1, 1, 200, 0, 0, 0, 1, 2, 3]);
+    let mut reader = CaptureReader::new(&truncated[..]).unwrap();
+    assert!(matches!(reader.next(), Some(Err(CaptureError::Io(_)))));
+    assert!(reader.next().is_none());
+
+    let mut frame = b"RDCAP\x02".to_vec();
+    frame.extend_from_slice(&[0; 8]);
+    frame.extend_from_slice(&[11, 0, 1, 1, 9, 0, 0, 0, 0]);
+    let mut reader = CaptureReader::new(&frame[..]).unwrap();
+    assert!(matches!(
+        reader.next(),
+        Some(Err(CaptureError::Decode(DecodeError::UnsupportedVersion(
+            9
+        ))))
+    ));
+
+    let mut direction = b"RDCAP\x02".to_vec();
+    direction.extend_from_slice(&[0; 8]);
+    direction.extend_from_slice(&[11, 7, 0, 0]);
+    let mut reader = CaptureReader::new(&direction[..]).unwrap();
+    assert!(matches!(
+        reader.next(),
+        Some(Err(CaptureError::InvalidRecord))
+    ));
+}
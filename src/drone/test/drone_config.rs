@@ -0,0 +1,142 @@
+#![cfg(test)]
+
+use crate::drone::{ConfigError, DroneBuilder, RustyDrone};
+use crossbeam_channel::{unbounded, Receiver};
+use rusty_tester::utils::data::{new_flood_request, new_flood_request_with_path};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::packet::{NodeType, Packet};
+
+fn full_builder() -> (DroneBuilder, Receiver<DroneEvent>) {
+    let (controller_send, event_recv) = unbounded::<DroneEvent>();
+    let (_, controller_recv) = unbounded::<DroneCommand>();
+    let (_, packet_recv) = unbounded::<Packet>();
+
+    let builder = RustyDrone::builder()
+        .id(11)
+        .controller_send(controller_send)
+        .controller_recv(controller_recv)
+        .packet_recv(packet_recv);
+
+    (builder, event_recv)
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn test_drone_builder() {
+    let (builder, _) = full_builder();
+    let drone = builder.pdr(0.25).build().unwrap();
+
+    assert_eq!(drone.id, 11);
+    assert_eq!(drone.pdr, 0.25);
+    assert!(drone.packet_send.is_empty());
+}
+
+#[test]
+fn test_drone_builder_missing_id() {
+    let (_, controller_recv) = unbounded::<DroneCommand>();
+    let (controller_send, _) = unbounded::<DroneEvent>();
+    let (_, packet_recv) = unbounded::<Packet>();
+
+    let res = RustyDrone::builder()
+        .controller_send(controller_send)
+        .controller_recv(controller_recv)
+        .packet_recv(packet_recv)
+        .build_config();
+    assert_eq!(res.err(), Some(ConfigError::MissingId));
+}
+
+#[test]
+fn test_drone_builder_missing_channel() {
+    let res = RustyDrone::builder().id(3).build_config();
+    assert_eq!(res.err(), Some(ConfigError::MissingControllerSend));
+}
+
+#[test]
+fn test_drone_builder_invalid_pdr() {
+    let (builder, _) = full_builder();
+    assert_eq!(
+        builder.pdr(1.5).build_config().err(),
+        Some(ConfigError::InvalidPdr(1.5))
+    );
+}
+
+#[test]
+fn test_drone_builder_no_packet_sent_for_flood() {
+    let (builder, event_recv) = full_builder();
+    let (exit_send1, exit_recv1) = unbounded();
+    let (exit_send2, _exit_recv2) = unbounded();
+
+    let mut drone = builder
+        .neighbor(12, exit_send1)
+        .neighbor(13, exit_send2)
+        .packet_sent_for_flood(false)
+        .build()
+        .unwrap();
+
+    drone.handle_packet(&new_flood_request(5, 7, 10, false), false);
+    assert_eq!(
+        new_flood_request_with_path(5, 7, 10, &[(11, NodeType::Drone)]),
+        exit_recv1.try_recv().unwrap()
+    );
+    assert!(event_recv.try_recv().is_err());
+}
+
+fn drop_sequence(drone: &mut RustyDrone) -> Vec<bool> {
+    (0..64).map(|_| drone.should_drop(2)).collect()
+}
+
+#[test]
+fn test_drone_builder_seed_reproducible() {
+    let (builder1, _) = full_builder();
+    let (builder2, _) = full_builder();
+    let mut drone1 = builder1.pdr(0.5).seed(42).build().unwrap();
+    let mut drone2 = builder2.pdr(0.5).seed(42).build().unwrap();
+
+    assert_eq!(drop_sequence(&mut drone1), drop_sequence(&mut drone2));
+}
+
+#[test]
+fn test_drone_builder_simulation_seed_per_drone() {
+    let (builder1, _) = full_builder();
+    let (builder2, _) = full_builder();
+    let (builder3, _) = full_builder();
+    let mut drone1 = builder1.pdr(0.5).simulation_seed(42).build().unwrap();
+    let mut drone2 = builder2.pdr(0.5).simulation_seed(42).build().unwrap();
+    let mut other = builder3
+        .id(12)
+        .pdr(0.5)
+        .simulation_seed(42)
+        .build()
+        .unwrap();
+
+    let sequence = drop_sequence(&mut drone1);
+    assert_eq!(sequence, drop_sequence(&mut drone2));
+    assert_ne!(sequence, drop_sequence(&mut other));
+}
+
+#[test]
+fn test_drone_builder_neighbor_pdr() {
+    let (builder, _) = full_builder();
+    let mut drone = builder.pdr(0.0).neighbor_pdr(12, 1.0).build().unwrap();
+
+    assert!(drone.should_drop(12));
+    assert!(!drone.should_drop(13));
+}
+
+#[test]
+fn test_drone_builder_invalid_neighbor_pdr() {
+    let (builder, _) = full_builder();
+    assert_eq!(
+        builder.neighbor_pdr(12, -0.5).build_config().err(),
+        Some(ConfigError::InvalidPdr(-0.5))
+    );
+}
+
+#[test]
+fn test_drone_builder_empty_flood_cache() {
+    let (builder, _) = full_builder();
+    assert_eq!(
+        builder.flood_cache(0, None).build_config().err(),
+        Some(ConfigError::EmptyFloodCache)
+    );
+}
@@ -0,0 +1,88 @@
+#![cfg(test)]
+
+use crate::drone::test::test_initialization;
+use crate::drone::{Bernoulli, ConfigError, DropModel, GilbertElliott, RustyDrone, TraceDriven};
+use crossbeam_channel::unbounded;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::packet::Packet;
+
+fn decisions(model: &mut dyn DropModel, pdr: f32, n: usize) -> Vec<bool> {
+    let mut rng = StdRng::seed_from_u64(7);
+    (0..n).map(|_| model.should_drop(pdr, &mut rng)).collect()
+}
+
+#[test]
+fn test_drop_model_bernoulli() {
+    assert_eq!(decisions(&mut Bernoulli, 0.0, 16), vec![false; 16]);
+    assert_eq!(decisions(&mut Bernoulli, 1.0, 16), vec![true; 16]);
+}
+
+#[test]
+fn test_drop_model_gilbert_elliott_burst() {
+    // Always switch to the bad state after the first packet and never recover
+    let mut model = GilbertElliott::new(1.0, 0.0, 0.0, 1.0).unwrap();
+
+    let mut expected = vec![true; 16];
+    expected[0] = false;
+    assert_eq!(decisions(&mut model, 0.0, 16), expected);
+}
+
+#[test]
+fn test_drop_model_gilbert_elliott_invalid() {
+    assert_eq!(
+        GilbertElliott::new(0.1, 2.0, 0.0, 1.0).err(),
+        Some(ConfigError::InvalidProbability(2.0))
+    );
+}
+
+#[test]
+fn test_drop_model_trace() {
+    let mut model = TraceDriven::parse("1 0, 0 # comment 1\n1").unwrap();
+    assert_eq!(
+        decisions(&mut model, 0.0, 6),
+        vec![true, false, false, true, true, false]
+    );
+}
+
+#[test]
+fn test_drop_model_trace_invalid() {
+    assert!(TraceDriven::parse("1 0 2").is_err());
+}
+
+#[test]
+fn test_drop_model_trace_empty() {
+    assert_eq!(
+        decisions(&mut TraceDriven::new(vec![]), 1.0, 4),
+        vec![false; 4]
+    );
+}
+
+#[test]
+fn test_drop_model_set_pdr_updates_bernoulli() {
+    let (_options, mut drone) = test_initialization();
+
+    assert!(!drone.should_drop(2));
+    drone.handle_commands(&DroneCommand::SetPacketDropRate(1.0));
+    assert!(drone.should_drop(2));
+}
+
+#[test]
+fn test_drop_model_builder() {
+    let (controller_send, _) = unbounded::<DroneEvent>();
+    let (_, controller_recv) = unbounded::<DroneCommand>();
+    let (_, packet_recv) = unbounded::<Packet>();
+
+    let mut drone = RustyDrone::builder()
+        .id(1)
+        .controller_send(controller_send)
+        .controller_recv(controller_recv)
+        .packet_recv(packet_recv)
+        .drop_model(TraceDriven::new(vec![false, true]))
+        .build()
+        .unwrap();
+
+    assert!(!drone.should_drop(2));
+    assert!(drone.should_drop(2));
+}
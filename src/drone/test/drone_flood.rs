@@ -46,7 +46,8 @@ fn test_drone_flood_res_inclusive() {
     );
 
     // Require extra node to be sure that it is not a leaf
-    let (options, mut drone, packet_exit, _) = simple_drone_with_two_exit(11, 1.0, 10, 13);
+    let (options, mut drone, packet_exit, _other_exit) =
+        simple_drone_with_two_exit(11, 1.0, 10, 13);
     drone.handle_packet(&packet, false);
     drone.handle_packet(&packet, false);
 
@@ -96,7 +97,8 @@ fn test_drone_flood_res() {
         },
     );
 
-    let (options, mut drone, packet_exit, _) = simple_drone_with_two_exit(11, 1.0, 10, 13);
+    let (options, mut drone, packet_exit, _other_exit) =
+        simple_drone_with_two_exit(11, 1.0, 10, 13);
     drone.handle_packet(&packet, false);
     drone.handle_packet(&packet, false);
 
@@ -137,3 +139,20 @@ fn test_drone_flood_res_leaf() {
     options.assert_expect_drone_event(&PacketSent(expected));
     options.assert_expect_drone_event_fail();
 }
+
+#[test]
+fn test_drone_flood_req_disconnected() {
+    let packet = new_flood_request(5, 7, 10, false);
+    let expected = new_flood_request_with_path(5, 7, 10, &[(11, NodeType::Drone)]);
+
+    let (options, mut drone, packet_exit, dead_exit) = simple_drone_with_two_exit(11, 1.0, 12, 13);
+    drop(dead_exit);
+
+    drone.handle_packet(&packet, false);
+    assert_eq!(expected, packet_exit.try_recv().unwrap());
+
+    // Only the flood request that was actually sent is reported
+    #[cfg(feature = "packet_sent_for_flood")]
+    assert_n_drone_event(&options, 1, &PacketSent(expected));
+    options.assert_expect_drone_event_fail();
+}
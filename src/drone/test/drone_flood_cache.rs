@@ -0,0 +1,58 @@
+#![cfg(test)]
+
+use crate::drone::flood_cache::FloodCache;
+use crate::drone::test::simple_drone_with_two_exit;
+use rusty_tester::utils::data::new_flood_request;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_flood_cache_duplicate() {
+    let mut cache = FloodCache::new(10, None);
+    let now = Instant::now();
+
+    assert!(!cache.insert((1, 10), now));
+    assert!(cache.insert((1, 10), now));
+    assert!(!cache.insert((1, 11), now));
+    assert!(!cache.insert((2, 10), now));
+    assert_eq!(cache.len(), 3);
+}
+
+#[test]
+fn test_flood_cache_capacity() {
+    let mut cache = FloodCache::new(2, None);
+    let now = Instant::now();
+
+    assert!(!cache.insert((1, 10), now));
+    assert!(!cache.insert((2, 10), now));
+    assert!(!cache.insert((3, 10), now));
+    assert_eq!(cache.len(), 2);
+
+    // The oldest was forgotten, the newest are still there
+    assert!(cache.insert((3, 10), now));
+    assert!(cache.insert((2, 10), now));
+    assert!(!cache.insert((1, 10), now));
+}
+
+#[test]
+fn test_flood_cache_ttl() {
+    let mut cache = FloodCache::new(10, Some(Duration::from_secs(5)));
+    let start = Instant::now();
+
+    assert!(!cache.insert((1, 10), start));
+    assert!(!cache.insert((2, 10), start + Duration::from_secs(3)));
+    assert!(cache.insert((1, 10), start + Duration::from_secs(4)));
+
+    assert!(!cache.insert((1, 10), start + Duration::from_secs(6)));
+    assert!(cache.insert((2, 10), start + Duration::from_secs(7)));
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn test_flood_cache_drone_len() {
+    let (_options, mut drone, _exit1, _exit2) = simple_drone_with_two_exit(11, 0.0, 12, 13);
+
+    drone.handle_packet(&new_flood_request(5, 7, 10, false), false);
+    drone.handle_packet(&new_flood_request(5, 7, 10, false), false);
+    drone.handle_packet(&new_flood_request(5, 8, 10, false), false);
+    assert_eq!(drone.flood_cache_len(), 2);
+}
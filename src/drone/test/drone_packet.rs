@@ -1,10 +1,11 @@
 #![cfg(test)]
 
 use crate::drone::test::{simple_drone_with_exit, simple_drone_with_two_exit};
+use crate::drone::MalformedRouting;
 use rusty_tester::utils::data::*;
 use rusty_tester::utils::Node;
 use wg_2024::controller::DroneEvent;
-use wg_2024::network::NodeId;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
 use wg_2024::packet::NackType::{Dropped, ErrorInRouting, UnexpectedRecipient};
 use wg_2024::packet::Packet;
 
@@ -138,3 +139,148 @@ fn test_drone_packet_unexpected_recepient() {
     let options = basic_single_hop_test(&packet, &expected_packet, false, 0.0, 11, 10);
     options.assert_expect_drone_event(&DroneEvent::PacketSent(expected_packet));
 }
+
+#[test]
+fn test_drone_packet_neighbor_pdr() {
+    let packet = new_test_fragment_packet(&[10, 11, 12], 5);
+    let expected_packet = new_forwarded(&packet);
+
+    // Link toward 12 is perfect even if the drone drops everything else
+    let (options, mut drone, packet_exit) = simple_drone_with_exit(11, 1.0, 12);
+    drone.neighbor_pdr().set(12, 0.0).unwrap();
+
+    drone.handle_packet(&packet, false);
+    assert_eq!(Ok(&expected_packet), packet_exit.try_recv().as_ref());
+    options.assert_expect_drone_event(&DroneEvent::PacketSent(expected_packet));
+    options.assert_expect_drone_event_fail();
+}
+
+#[test]
+fn test_drone_packet_neighbor_pdr_dropped() {
+    let packet = new_test_fragment_packet(&[10, 11, 12], 5);
+    let expected = new_test_nack(&[11, 10], Dropped, 5, 1);
+
+    let (options, mut drone, packet_exit, _) = simple_drone_with_two_exit(11, 0.0, 10, 12);
+    drone.neighbor_pdr().set(12, 1.0).unwrap();
+
+    drone.handle_packet(&packet, false);
+    assert_eq!(expected, packet_exit.try_recv().unwrap());
+    options.assert_expect_drone_event(&DroneEvent::PacketDropped(packet));
+    options.assert_expect_drone_event(&DroneEvent::PacketSent(expected));
+    options.assert_expect_drone_event_fail();
+}
+
+#[test]
+fn test_drone_packet_forward_to_disconnected() {
+    let packet = new_test_fragment_packet(&[10, 11, 12], 5);
+    let expected = new_test_nack(&[11, 10], ErrorInRouting(12), 5, 1);
+
+    let (options, mut drone, packet_exit, dead_exit) = simple_drone_with_two_exit(11, 0.0, 10, 12);
+    drop(dead_exit);
+
+    drone.handle_packet(&packet, false);
+    assert_eq!(expected, packet_exit.try_recv().unwrap());
+
+    options.assert_expect_drone_event(&DroneEvent::PacketSent(expected));
+    options.assert_expect_drone_event_fail();
+    assert!(drone.packet_send.contains_key(&12));
+}
+
+#[test]
+fn test_drone_packet_nack_to_disconnected_shortcut() {
+    let packet = new_test_nack(&[10, 11, 12], Dropped, 5, 1);
+
+    let (options, mut drone, dead_exit) = simple_drone_with_exit(11, 0.0, 12);
+    drop(dead_exit);
+
+    drone.handle_packet(&packet, false);
+    options.assert_expect_drone_event(&DroneEvent::ControllerShortcut(new_forwarded(&packet)));
+    options.assert_expect_drone_event_fail();
+}
+
+#[test]
+fn test_drone_packet_nack_back_to_disconnected_shortcut() {
+    let packet = new_test_fragment_packet(&[10, 11, 12], 5);
+    let expected = new_test_nack(&[11, 10], ErrorInRouting(12), 5, 1);
+
+    let (options, mut drone, dead_exit) = simple_drone_with_exit(11, 0.0, 10);
+    drop(dead_exit);
+
+    drone.handle_packet(&packet, false);
+    options.assert_expect_drone_event(&DroneEvent::ControllerShortcut(expected));
+    options.assert_expect_drone_event_fail();
+}
+
+#[test]
+fn test_drone_packet_prune_disconnected() {
+    let packet = new_test_fragment_packet(&[10, 11, 12], 5);
+
+    let (_options, mut drone, _exit, dead_exit) = simple_drone_with_two_exit(11, 0.0, 10, 12);
+    drone.prune_dead_neighbors = true;
+    drop(dead_exit);
+
+    drone.handle_packet(&packet, false);
+    assert!(!drone.packet_send.contains_key(&12));
+    assert_eq!(drone.stats().snapshot().send_failures.get(&12), Some(&1));
+}
+
+fn with_routing(packet: &Packet, hops: &[NodeId], hop_index: usize) -> Packet {
+    let mut packet = packet.clone();
+    packet.routing_header = SourceRoutingHeader::new(hops.to_vec(), hop_index);
+    packet
+}
+
+#[test]
+fn test_drone_packet_malformed_empty_route() {
+    let fragment = with_routing(&new_test_fragment_packet(&[10, 11, 12], 5), &[], 1);
+    let nack = with_routing(&new_test_nack(&[10, 11, 12], Dropped, 5, 1), &[], 0);
+
+    let options = basic_single_hop_test_fail(&fragment, false, 0.0, 11, 10);
+    options.assert_expect_drone_event(&DroneEvent::PacketDropped(fragment));
+    options.assert_expect_drone_event_fail();
+    let options = basic_single_hop_test_fail(&nack, false, 0.0, 11, 10);
+    options.assert_expect_drone_event_fail();
+}
+
+#[test]
+fn test_drone_packet_malformed_hop_index() {
+    for hop_index in [0, 3, 4, usize::MAX] {
+        let fragment = with_routing(
+            &new_test_fragment_packet(&[10, 11, 12], 5),
+            &[10, 11, 12],
+            hop_index,
+        );
+        let options = basic_single_hop_test_fail(&fragment, false, 0.0, 11, 10);
+        options.assert_expect_drone_event(&DroneEvent::PacketDropped(fragment));
+        options.assert_expect_drone_event_fail();
+
+        let nack = with_routing(
+            &new_test_nack(&[10, 11, 12], Dropped, 5, 1),
+            &[11, 12],
+            hop_index,
+        );
+        let options = basic_single_hop_test_fail(&nack, false, 0.0, 11, 12);
+        options.assert_expect_drone_event(&DroneEvent::ControllerShortcut(nack));
+        options.assert_expect_drone_event_fail();
+    }
+}
+
+#[test]
+fn test_drone_packet_malformed_stats() {
+    let fragment = with_routing(
+        &new_test_fragment_packet(&[10, 11, 12], 5),
+        &[10, 11, 12],
+        7,
+    );
+
+    let (_options, mut drone, _exit) = simple_drone_with_exit(11, 0.0, 10);
+    drone.handle_packet(&fragment, false);
+    drone.handle_packet(&with_routing(&fragment, &[], 0), true);
+
+    let malformed = drone.stats().snapshot().malformed;
+    assert_eq!(
+        malformed.get(&MalformedRouting::HopIndexOutOfRange),
+        Some(&1)
+    );
+    assert_eq!(malformed.get(&MalformedRouting::EmptyRoute), Some(&1));
+}
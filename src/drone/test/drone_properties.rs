@@ -0,0 +1,147 @@
+#![cfg(test)]
+
+use crate::drone::test::test_initialization_with_value;
+use crate::drone::RustyDrone;
+use crossbeam_channel::{unbounded, Receiver};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use rusty_tester::utils::data::new_test_fragment_packet;
+use rusty_tester::utils::Node;
+use std::collections::HashMap;
+use wg_2024::controller::DroneCommand;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{FloodRequest, NodeType, Packet, PacketType};
+
+/// Id of the tested drone, never generated for the other nodes.
+const DRONE_ID: NodeId = 200;
+
+fn drone_with_neighbors(
+    neighbors: &[NodeId],
+) -> (Node, RustyDrone, HashMap<NodeId, Receiver<Packet>>) {
+    let (options, mut drone) = test_initialization_with_value(DRONE_ID, 0.0);
+
+    let mut exits = HashMap::new();
+    for neighbor in neighbors {
+        let (sender, receiver) = unbounded();
+        drone.handle_commands(&DroneCommand::AddSender(*neighbor, sender));
+        exits.insert(*neighbor, receiver);
+    }
+
+    (options, drone, exits)
+}
+
+fn flood_packet(session_id: u64, request: FloodRequest) -> Packet {
+    Packet::new_flood_request(SourceRoutingHeader::new(vec![], 0), session_id, request)
+}
+
+fn node_type() -> impl Strategy<Value = NodeType> {
+    prop_oneof![
+        Just(NodeType::Client),
+        Just(NodeType::Drone),
+        Just(NodeType::Server)
+    ]
+}
+
+/// Route of at least 3 nodes with the drone at a position that is neither the first nor the last.
+fn route_through_drone() -> impl Strategy<Value = (Vec<NodeId>, usize)> {
+    vec(0..DRONE_ID, 2..12)
+        .prop_flat_map(|hops| {
+            let len = hops.len();
+            (Just(hops), 1..len)
+        })
+        .prop_map(|(mut hops, hop_index)| {
+            hops.insert(hop_index, DRONE_ID);
+            (hops, hop_index)
+        })
+}
+
+proptest! {
+    #[test]
+    fn prop_routing_back_ends_at_sender((hops, hop_index) in route_through_drone()) {
+        let (_options, drone) = test_initialization_with_value(DRONE_ID, 0.0);
+        let routing = SourceRoutingHeader::new(hops.clone(), hop_index);
+
+        let back = drone.get_routing_back(&routing);
+
+        prop_assert_eq!(back.hop_index, 1);
+        prop_assert_eq!(back.hops.first(), Some(&DRONE_ID));
+        prop_assert_eq!(back.hops.last(), hops.first());
+        prop_assert_eq!(back.hops.len(), hop_index + 1);
+        prop_assert!(back.hops[1..].iter().eq(hops[..hop_index].iter().rev()));
+    }
+
+    #[test]
+    fn prop_forward_increases_hop_index(
+        (hops, hop_index) in route_through_drone(),
+        session in any::<u64>(),
+    ) {
+        let next = hops[hop_index + 1];
+        let (_options, mut drone, exits) = drone_with_neighbors(&[next]);
+
+        let mut packet = new_test_fragment_packet(&hops, session);
+        packet.routing_header.hop_index = hop_index;
+        drone.handle_packet(&packet, false);
+
+        let forwarded = exits[&next].try_recv().unwrap();
+        prop_assert_eq!(forwarded.routing_header.hop_index, hop_index + 1);
+        prop_assert_eq!(&forwarded.routing_header.hops, &hops);
+        prop_assert_eq!(forwarded.pack_type, packet.pack_type);
+    }
+
+    #[test]
+    fn prop_flood_response_reverses_path(
+        path in vec((0..DRONE_ID, node_type()), 1..12),
+        flood_id in any::<u64>(),
+        session in any::<u64>(),
+    ) {
+        let initiator = path[0].0;
+        let previous = path.last().unwrap().0;
+        // Only neighbor is the one that sent the request, so it must be terminated
+        let (_options, mut drone, exits) = drone_with_neighbors(&[previous]);
+
+        let request = FloodRequest { flood_id, initiator_id: initiator, path_trace: path.clone() };
+        drone.handle_packet(&flood_packet(session, request), false);
+
+        let response = exits[&previous].try_recv().unwrap();
+        let PacketType::FloodResponse(ref flood) = response.pack_type else {
+            panic!("expected a flood response, got {response:?}");
+        };
+
+        let mut expected_trace = path.clone();
+        expected_trace.push((DRONE_ID, NodeType::Drone));
+        prop_assert_eq!(&flood.path_trace, &expected_trace);
+        prop_assert_eq!(response.routing_header.hop_index, 1);
+        prop_assert!(response
+            .routing_header
+            .hops
+            .iter()
+            .eq(expected_trace.iter().rev().map(|(id, _)| id)));
+    }
+
+    #[test]
+    fn prop_flood_forwarded_once(
+        floods in vec((0..4u64, 0..4 as NodeId, 0..4usize), 1..40),
+    ) {
+        let neighbors = [0, 1, 2, 3];
+        let (_options, mut drone, exits) = drone_with_neighbors(&neighbors);
+
+        for (flood_id, initiator, previous) in floods {
+            let request = FloodRequest {
+                flood_id,
+                initiator_id: initiator,
+                path_trace: vec![(neighbors[previous], NodeType::Drone)],
+            };
+            drone.handle_packet(&flood_packet(1, request), false);
+        }
+
+        for receiver in exits.values() {
+            let mut forwarded = HashMap::new();
+            for packet in receiver.try_iter() {
+                if let PacketType::FloodRequest(flood) = packet.pack_type {
+                    *forwarded.entry((flood.flood_id, flood.initiator_id)).or_insert(0) += 1;
+                }
+            }
+            prop_assert!(forwarded.values().all(|count| *count == 1));
+        }
+    }
+}
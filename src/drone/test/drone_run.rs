@@ -0,0 +1,215 @@
+#![cfg(test)]
+
+use crate::drone::{
+    ControllerDisconnect, DrainPolicy, DroneBuilder, DroneExit, ExitReason, RustyDrone,
+};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use rusty_tester::utils::data::{new_forwarded, new_test_fragment_packet, new_test_nack};
+use std::thread;
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::packet::NackType::ErrorInRouting;
+use wg_2024::packet::Packet;
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+struct Harness {
+    command_send: Sender<DroneCommand>,
+    packet_send: Sender<Packet>,
+    _event_recv: Receiver<DroneEvent>,
+}
+
+fn run_builder(id: u8) -> (Harness, DroneBuilder) {
+    let (controller_send, event_recv) = unbounded::<DroneEvent>();
+    let (command_send, controller_recv) = unbounded::<DroneCommand>();
+    let (packet_send, packet_recv) = unbounded::<Packet>();
+
+    let builder = RustyDrone::builder()
+        .id(id)
+        .controller_send(controller_send)
+        .controller_recv(controller_recv)
+        .packet_recv(packet_recv);
+
+    let harness = Harness {
+        command_send,
+        packet_send,
+        _event_recv: event_recv,
+    };
+    (harness, builder)
+}
+
+/// Run the drone on its own thread, the receiver gets a message when `run` returns.
+fn spawn(mut drone: RustyDrone) -> Receiver<()> {
+    let (done_send, done_recv) = unbounded();
+    thread::spawn(move || {
+        drone.run();
+        let _ = done_send.send(());
+    });
+    done_recv
+}
+
+#[test]
+fn test_drone_run_exit_when_all_disconnected() {
+    let (harness, builder) = run_builder(11);
+    let done = spawn(builder.build().unwrap());
+
+    drop(harness.packet_send);
+    assert!(done.recv_timeout(TIMEOUT / 4).is_err());
+
+    drop(harness.command_send);
+    assert!(done.recv_timeout(TIMEOUT).is_ok());
+}
+
+#[test]
+fn test_drone_run_controller_disconnect_crash() {
+    let (exit_send, exit_recv) = unbounded();
+    let (harness, builder) = run_builder(11);
+    let done = spawn(builder.neighbor(10, exit_send).build().unwrap());
+
+    drop(harness.command_send);
+    thread::sleep(TIMEOUT / 4);
+
+    // Drone behaves as crashed
+    let packet = new_test_fragment_packet(&[10, 11, 12], 5);
+    harness.packet_send.send(packet).unwrap();
+    assert_eq!(
+        new_test_nack(&[11, 10], ErrorInRouting(11), 5, 1),
+        exit_recv.recv_timeout(TIMEOUT).unwrap()
+    );
+
+    drop(harness.packet_send);
+    assert!(done.recv_timeout(TIMEOUT).is_ok());
+}
+
+#[test]
+fn test_drone_run_controller_disconnect_forward_only() {
+    let (exit_send, exit_recv) = unbounded();
+    let (harness, builder) = run_builder(11);
+    let builder = builder
+        .neighbor(12, exit_send)
+        .controller_disconnect(ControllerDisconnect::ForwardOnly);
+    let done = spawn(builder.build().unwrap());
+
+    drop(harness.command_send);
+    thread::sleep(TIMEOUT / 4);
+
+    // Drone still forwards normally
+    let packet = new_test_fragment_packet(&[10, 11, 12], 5);
+    harness.packet_send.send(packet.clone()).unwrap();
+    assert_eq!(
+        new_forwarded(&packet),
+        exit_recv.recv_timeout(TIMEOUT).unwrap()
+    );
+    assert!(done.try_recv().is_err());
+
+    drop(harness.packet_send);
+    assert!(done.recv_timeout(TIMEOUT).is_ok());
+}
+
+#[test]
+fn test_drone_run_exit_event_disconnected() {
+    let (exit_send, exit_recv) = unbounded();
+    let (harness, builder) = run_builder(11);
+    let builder = builder
+        .exit_send(exit_send)
+        .controller_disconnect(ControllerDisconnect::ForwardOnly);
+    let done = spawn(builder.build().unwrap());
+
+    drop(harness.command_send);
+    drop(harness.packet_send);
+    assert!(done.recv_timeout(TIMEOUT).is_ok());
+    assert_eq!(
+        Ok(DroneExit {
+            id: 11,
+            reason: ExitReason::Disconnected
+        }),
+        exit_recv.try_recv()
+    );
+}
+
+#[test]
+fn test_drone_run_drain_until_disconnected() {
+    let (exit_send, exit_recv) = unbounded();
+    let (harness, builder) = run_builder(11);
+    let done = spawn(builder.exit_send(exit_send).build().unwrap());
+
+    harness.command_send.send(DroneCommand::Crash).unwrap();
+    assert!(done.recv_timeout(TIMEOUT / 4).is_err());
+
+    drop(harness.packet_send);
+    assert!(done.recv_timeout(TIMEOUT).is_ok());
+    assert_eq!(
+        Ok(ExitReason::Crashed),
+        exit_recv.try_recv().map(|e| e.reason)
+    );
+}
+
+#[test]
+fn test_drone_run_drain_timeout() {
+    let (exit_send, exit_recv) = unbounded();
+    let (harness, builder) = run_builder(11);
+    let builder = builder
+        .exit_send(exit_send)
+        .drain(DrainPolicy::Timeout(TIMEOUT / 4));
+    let done = spawn(builder.build().unwrap());
+
+    // A neighbor never removes us, but the drone exits anyway
+    harness.command_send.send(DroneCommand::Crash).unwrap();
+    assert!(done.recv_timeout(TIMEOUT).is_ok());
+    assert_eq!(
+        Ok(ExitReason::Crashed),
+        exit_recv.try_recv().map(|e| e.reason)
+    );
+    drop(harness.packet_send);
+}
+
+#[test]
+fn test_drone_run_drain_until_empty() {
+    let (exit_send, exit_recv) = unbounded();
+    let (harness, builder) = run_builder(11);
+    let tested = builder
+        .neighbor(10, exit_send)
+        .drain(DrainPolicy::UntilEmpty)
+        .build()
+        .unwrap();
+
+    // Queued before crashing, still handled as crashed drone
+    let packet = new_test_fragment_packet(&[10, 11, 12], 5);
+    harness.packet_send.send(packet).unwrap();
+    harness.command_send.send(DroneCommand::Crash).unwrap();
+
+    let done = spawn(tested);
+    assert!(done.recv_timeout(TIMEOUT).is_ok());
+    assert_eq!(
+        Ok(new_test_nack(&[11, 10], ErrorInRouting(11), 5, 1)),
+        exit_recv.try_recv()
+    );
+    drop(harness.packet_send);
+}
+
+#[test]
+fn test_drone_run_packet_spans() {
+    let (span_send, span_recv) = unbounded();
+    let (neighbor_send, neighbor_recv) = unbounded();
+    let (harness, builder) = run_builder(11);
+    let builder = builder.span_send(span_send).neighbor(12, neighbor_send);
+    let _done = spawn(builder.build().unwrap());
+
+    let packet = new_test_fragment_packet(&[1, 11, 12], 5);
+    harness.packet_send.send(packet.clone()).unwrap();
+    assert_eq!(
+        neighbor_recv.recv_timeout(TIMEOUT),
+        Ok(new_forwarded(&packet))
+    );
+
+    let span = span_recv.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(span.id, 11);
+    assert_eq!(span.packet, packet);
+    assert!(!span.crashing);
+    assert!(span.start.elapsed() >= span.duration);
+
+    harness.command_send.send(DroneCommand::Crash).unwrap();
+    harness.packet_send.send(packet).unwrap();
+    assert!(span_recv.recv_timeout(TIMEOUT).unwrap().crashing);
+}
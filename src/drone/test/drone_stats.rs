@@ -0,0 +1,68 @@
+#![cfg(test)]
+
+use crate::drone::test::{simple_drone_with_exit, simple_drone_with_two_exit};
+use crate::drone::{NackKind, PacketKind};
+use rusty_tester::utils::data::{new_flood_request, new_test_fragment_packet, new_test_nack};
+use std::collections::HashMap;
+use wg_2024::packet::NackType::Dropped;
+
+#[test]
+fn test_drone_stats_forward() {
+    let (_options, mut drone, _exit) = simple_drone_with_exit(11, 0.0, 12);
+    let stats = drone.stats();
+
+    drone.handle_packet(&new_test_fragment_packet(&[10, 11, 12], 5), false);
+    drone.handle_packet(&new_test_nack(&[10, 11, 12], Dropped, 5, 1), false);
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.received.get(&PacketKind::MsgFragment), Some(&1));
+    assert_eq!(snapshot.received.get(&PacketKind::Nack), Some(&1));
+    assert_eq!(snapshot.sent.get(&PacketKind::MsgFragment), Some(&1));
+    assert_eq!(snapshot.sent.get(&PacketKind::Nack), Some(&1));
+    assert_eq!(snapshot.sent_to, HashMap::from([(12, 2)]));
+    assert_eq!(snapshot.dropped(), 0);
+    assert!(snapshot.nacks.is_empty());
+}
+
+#[test]
+fn test_drone_stats_dropped() {
+    let (_options, mut drone, _exit1, _exit2) = simple_drone_with_two_exit(11, 1.0, 10, 12);
+
+    drone.handle_packet(&new_test_fragment_packet(&[10, 11, 12], 5), false);
+
+    let snapshot = drone.stats().snapshot();
+    assert_eq!(snapshot.dropped_toward, HashMap::from([(12, 1)]));
+    assert_eq!(snapshot.nacks, HashMap::from([(NackKind::Dropped, 1)]));
+    assert_eq!(snapshot.sent_to, HashMap::from([(10, 1)]));
+}
+
+#[test]
+fn test_drone_stats_error_in_routing() {
+    let (_options, mut drone, _exit) = simple_drone_with_exit(11, 0.0, 10);
+
+    drone.handle_packet(&new_test_fragment_packet(&[10, 11, 12], 5), false);
+    drone.handle_packet(&new_test_nack(&[10, 11, 12], Dropped, 5, 1), false);
+
+    let snapshot = drone.stats().snapshot();
+    assert_eq!(
+        snapshot.nacks,
+        HashMap::from([(NackKind::ErrorInRouting, 1)])
+    );
+    assert_eq!(snapshot.shortcuts, 1);
+}
+
+#[test]
+fn test_drone_stats_flood() {
+    let (_options, mut drone, _exit1, _exit2) = simple_drone_with_two_exit(11, 0.0, 10, 12);
+
+    drone.handle_packet(&new_flood_request(5, 7, 10, false), false);
+    drone.handle_packet(&new_flood_request(5, 7, 10, false), false);
+
+    let snapshot = drone.stats().snapshot();
+    assert_eq!(snapshot.received.get(&PacketKind::FloodRequest), Some(&2));
+    assert_eq!(snapshot.floods_forwarded, 1);
+    assert_eq!(snapshot.floods_terminated, 1);
+    assert_eq!(snapshot.flood_cache_size, 1);
+    assert_eq!(snapshot.sent.get(&PacketKind::FloodRequest), Some(&1));
+    assert_eq!(snapshot.sent.get(&PacketKind::FloodResponse), Some(&1));
+}
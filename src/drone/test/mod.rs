@@ -1,7 +1,14 @@
 #![cfg(test)]
+mod drone_capture;
 mod drone_command;
+mod drone_config;
+mod drone_drop_model;
 mod drone_flood;
+mod drone_flood_cache;
 mod drone_packet;
+mod drone_properties;
+mod drone_run;
+mod drone_stats;
 
 use crate::drone::RustyDrone;
 use crossbeam_channel::{unbounded, Receiver};
@@ -1,6 +1,6 @@
-use crate::drone::RustyDrone;
-use rand::Rng;
-use wg_2024::network::SourceRoutingHeader;
+use crate::drone::{MalformedRouting, RustyDrone};
+use std::time::Instant;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
 use wg_2024::packet::{FloodRequest, PacketType};
 
 macro_rules! extract {
@@ -13,18 +13,16 @@ macro_rules! extract {
 }
 
 impl RustyDrone {
-    #[allow(deprecated)]
-    /// Decides if this packet should be dropped according to the packet drop rate.
-    pub(super) fn should_drop(&self) -> bool {
-        let mut rng = rand::thread_rng();
-        rng.gen_range(0.0..1.0) < self.pdr
+    /// Decides if a packet sent to `next_hop` should be dropped according to the drop model.
+    pub(super) fn should_drop(&mut self, next_hop: NodeId) -> bool {
+        let pdr = self.neighbor_pdr.get(next_hop).unwrap_or(self.pdr);
+        self.drop_model.should_drop(pdr, &mut self.rng)
     }
 
     /// Adds the flood request to the received flood requests, returns if the flood request was already present before.
     pub(super) fn already_received_flood(&mut self, flood: &FloodRequest) -> bool {
-        !self
-            .received_floods
-            .insert((flood.flood_id, flood.initiator_id))
+        self.received_floods
+            .insert((flood.flood_id, flood.initiator_id), Instant::now())
     }
 
     /// Calculates the route to send a packet back to the sender of a packet (according to its routing).
@@ -34,16 +32,33 @@ impl RustyDrone {
             .hops
             .iter()
             .copied()
-            .take(routing.hop_index + 1)
+            .take(routing.hop_index.saturating_add(1))
             .rev()
             .collect::<Vec<_>>();
 
-        hops[0] = self.id;
+        match hops.first_mut() {
+            Some(first) => *first = self.id,
+            None => hops.push(self.id),
+        }
 
         SourceRoutingHeader { hops, hop_index: 1 }
     }
 }
 
+/// Check that the routing header of a packet received from a neighbor can be followed.
+/// Does not check that we are the current hop.
+pub(super) fn validate_routing(routing: &SourceRoutingHeader) -> Result<(), MalformedRouting> {
+    if routing.hops.is_empty() {
+        Err(MalformedRouting::EmptyRoute)
+    } else if routing.hop_index == 0 {
+        Err(MalformedRouting::NoPreviousHop)
+    } else if routing.hop_index >= routing.hops.len() {
+        Err(MalformedRouting::HopIndexOutOfRange)
+    } else {
+        Ok(())
+    }
+}
+
 /// Get fragment index of `MsgFragment` or use default fragment index 0 for other packet types.
 pub(super) fn get_fragment_index(packet_type: &PacketType) -> u64 {
     extract!(packet_type, PacketType::MsgFragment).map_or(0, |x| x.fragment_index)
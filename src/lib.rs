@@ -1,5 +1,15 @@
 #![warn(clippy::pedantic)]
+pub mod codec;
 mod drone;
 mod integration_testing;
+pub mod process;
+pub mod simulation;
+pub mod transport;
 
-pub use drone::RustyDrone;
+pub use drone::{
+    load_capture, Bernoulli, Capture, CaptureError, CaptureReader, CapturedPacket, ConfigError,
+    ControllerDisconnect, Direction, DrainPolicy, DroneBuilder, DroneConfig, DroneExit,
+    DroneOptions, DroneStats, DroneStatsHandle, DropModel, ExitReason, GilbertElliott,
+    MalformedRouting, NackKind, NeighborPdr, PacketKind, PacketSpan, RngSeed, RustyDrone,
+    TraceDriven,
+};
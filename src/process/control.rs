@@ -0,0 +1,162 @@
+//! Messages on the control socket between the launcher and a drone process.
+//!
+//! Every message is its length (u32, little endian) followed by a tag and its fields.
+//! Packets are [`codec`] frames and addresses are UTF-8 strings prefixed by their length (u8).
+
+use crate::codec;
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::net::SocketAddr;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Longest message accepted, a packet frame and a few fields.
+const MAX_MESSAGE_LEN: usize = codec::MAX_FRAME_LEN + 64;
+
+/// Sent by the launcher to a drone process.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum RemoteCommand {
+    /// Send packets for the node to this address.
+    AddSender(NodeId, SocketAddr),
+    RemoveSender(NodeId),
+    SetPacketDropRate(f32),
+    Crash,
+    /// Answer with [`Report::Synced`] once the previous commands were handed to the drone.
+    Sync,
+}
+
+/// Sent by a drone process to the launcher.
+#[derive(Debug, Clone)]
+pub(super) enum Report {
+    /// First message, with the address the drone receives packets on.
+    Hello(NodeId, SocketAddr),
+    Event(DroneEvent),
+    Synced,
+}
+
+pub(super) fn write_command(writer: &mut impl Write, command: &RemoteCommand) -> io::Result<()> {
+    let mut body = vec![];
+    match command {
+        RemoteCommand::AddSender(id, address) => {
+            body.extend_from_slice(&[0, *id]);
+            put_address(&mut body, *address);
+        }
+        RemoteCommand::RemoveSender(id) => body.extend_from_slice(&[1, *id]),
+        RemoteCommand::SetPacketDropRate(pdr) => {
+            body.push(2);
+            body.extend_from_slice(&pdr.to_le_bytes());
+        }
+        RemoteCommand::Crash => body.push(3),
+        RemoteCommand::Sync => body.push(4),
+    }
+    write_message(writer, &body)
+}
+
+/// # Returns
+/// The next command, `None` if the launcher closed the socket.
+pub(super) fn read_command(reader: &mut impl Read) -> io::Result<Option<RemoteCommand>> {
+    let Some(body) = read_message(reader)? else {
+        return Ok(None);
+    };
+    let (tag, fields) = body.split_first().ok_or_else(invalid)?;
+    let command = match (tag, fields) {
+        (0, [id, address @ ..]) => RemoteCommand::AddSender(*id, get_address(address)?),
+        (1, [id]) => RemoteCommand::RemoveSender(*id),
+        (2, [a, b, c, d]) => RemoteCommand::SetPacketDropRate(f32::from_le_bytes([*a, *b, *c, *d])),
+        (3, []) => RemoteCommand::Crash,
+        (4, []) => RemoteCommand::Sync,
+        _ => return Err(invalid()),
+    };
+    Ok(Some(command))
+}
+
+pub(super) fn write_report(writer: &mut impl Write, report: &Report) -> io::Result<()> {
+    let mut body = vec![];
+    match report {
+        Report::Hello(id, address) => {
+            body.extend_from_slice(&[0, *id]);
+            put_address(&mut body, *address);
+        }
+        Report::Event(DroneEvent::PacketSent(packet)) => put_packet(&mut body, 1, packet)?,
+        Report::Event(DroneEvent::PacketDropped(packet)) => put_packet(&mut body, 2, packet)?,
+        Report::Event(DroneEvent::ControllerShortcut(packet)) => put_packet(&mut body, 3, packet)?,
+        Report::Synced => body.push(4),
+    }
+    write_message(writer, &body)
+}
+
+/// # Returns
+/// The next report, `None` if the drone process closed the socket.
+pub(super) fn read_report(reader: &mut impl Read) -> io::Result<Option<Report>> {
+    let Some(body) = read_message(reader)? else {
+        return Ok(None);
+    };
+    let (tag, fields) = body.split_first().ok_or_else(invalid)?;
+    let report = match (tag, fields) {
+        (0, [id, address @ ..]) => Report::Hello(*id, get_address(address)?),
+        (1, frame) => Report::Event(DroneEvent::PacketSent(get_packet(frame)?)),
+        (2, frame) => Report::Event(DroneEvent::PacketDropped(get_packet(frame)?)),
+        (3, frame) => Report::Event(DroneEvent::ControllerShortcut(get_packet(frame)?)),
+        (4, []) => Report::Synced,
+        _ => return Err(invalid()),
+    };
+    Ok(Some(report))
+}
+
+fn write_message(writer: &mut impl Write, body: &[u8]) -> io::Result<()> {
+    // Bodies are always shorter than MAX_MESSAGE_LEN
+    let len = u32::try_from(body.len()).unwrap_or(u32::MAX);
+    let mut message = Vec::with_capacity(4 + body.len());
+    message.extend_from_slice(&len.to_le_bytes());
+    message.extend_from_slice(body);
+    writer.write_all(&message)?;
+    writer.flush()
+}
+
+fn read_message(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    match reader.read_exact(&mut len) {
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        result => result?,
+    }
+    let len = usize::try_from(u32::from_le_bytes(len)).unwrap_or(usize::MAX);
+    if len > MAX_MESSAGE_LEN {
+        return Err(invalid());
+    }
+
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn put_address(body: &mut Vec<u8>, address: SocketAddr) {
+    // At most 47 bytes, for an IPv6 address with scope and port
+    let address = address.to_string();
+    body.push(u8::try_from(address.len()).unwrap_or(u8::MAX));
+    body.extend_from_slice(address.as_bytes());
+}
+
+fn get_address(bytes: &[u8]) -> io::Result<SocketAddr> {
+    let (len, address) = bytes.split_first().ok_or_else(invalid)?;
+    if address.len() != usize::from(*len) {
+        return Err(invalid());
+    }
+    std::str::from_utf8(address)
+        .ok()
+        .and_then(|address| address.parse().ok())
+        .ok_or_else(invalid)
+}
+
+fn put_packet(body: &mut Vec<u8>, tag: u8, packet: &Packet) -> io::Result<()> {
+    body.push(tag);
+    codec::encode_into(packet, body).map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))
+}
+
+fn get_packet(frame: &[u8]) -> io::Result<Packet> {
+    codec::decode(frame).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+}
+
+fn invalid() -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, "invalid control message")
+}
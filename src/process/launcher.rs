@@ -0,0 +1,666 @@
+use crate::process::control::{read_report, write_command, RemoteCommand, Report};
+use crate::process::DroneProcess;
+use crate::simulation::{validate_config, NetworkConfigError, NodeKind, SimulationError, Topology};
+use crate::transport::{Transport, UdpTransport};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use std::{fs, io, thread};
+use wg_2024::config::Config;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Reason why a drone process cannot be started or controlled.
+#[derive(Debug)]
+pub enum LaunchError {
+    Io(io::Error),
+    /// The network initialization file is not valid.
+    Config(NetworkConfigError),
+    Simulation(SimulationError),
+    /// The drone process did not connect to the launcher in time.
+    Timeout(NodeId),
+    /// The drone process exited before connecting to the launcher.
+    Exited(NodeId, ExitStatus),
+}
+
+impl Display for LaunchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LaunchError::Io(err) => write!(f, "{err}"),
+            LaunchError::Config(err) => write!(f, "{err}"),
+            LaunchError::Simulation(err) => write!(f, "{err}"),
+            LaunchError::Timeout(id) => write!(f, "drone {id} did not connect in time"),
+            LaunchError::Exited(id, status) => {
+                write!(f, "drone {id} exited before connecting ({status})")
+            }
+        }
+    }
+}
+
+impl Error for LaunchError {}
+
+impl From<io::Error> for LaunchError {
+    fn from(err: io::Error) -> Self {
+        LaunchError::Io(err)
+    }
+}
+
+impl From<NetworkConfigError> for LaunchError {
+    fn from(err: NetworkConfigError) -> Self {
+        LaunchError::Config(err)
+    }
+}
+
+impl From<SimulationError> for LaunchError {
+    fn from(err: SimulationError) -> Self {
+        LaunchError::Simulation(err)
+    }
+}
+
+/// Starts every drone of a network as a child process running [`DroneProcess`].
+#[derive(Debug, Clone)]
+pub struct Launcher {
+    /// Executable of the drone processes, like the `rusty-drone` binary.
+    program: PathBuf,
+    log_dir: PathBuf,
+    seed: Option<u64>,
+    drain: Duration,
+    connect_timeout: Duration,
+}
+
+impl Launcher {
+    /// Launcher of the drone executable, logging in a new directory of the temporary one.
+    #[must_use]
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        static LAUNCHED: AtomicUsize = AtomicUsize::new(0);
+        let n = LAUNCHED.fetch_add(1, Ordering::Relaxed);
+        Self {
+            program: program.into(),
+            log_dir: std::env::temp_dir().join(format!("rusty_drones_{}_{n}", std::process::id())),
+            seed: None,
+            drain: Duration::from_millis(100),
+            connect_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Directory of the logs, one file per drone.
+    #[must_use]
+    pub fn log_dir(mut self, log_dir: impl Into<PathBuf>) -> Self {
+        self.log_dir = log_dir.into();
+        self
+    }
+
+    /// Simulation seed given to every drone.
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// How long a crashed drone keeps handling its queued packets.
+    #[must_use]
+    pub fn drain(mut self, drain: Duration) -> Self {
+        self.drain = drain;
+        self
+    }
+
+    /// How long to wait for a started drone to connect.
+    #[must_use]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Validate the configuration, then launch the network it describes.
+    /// # Errors
+    /// If the configuration is not valid or a drone cannot be started.
+    pub fn launch_config(&self, config: &Config) -> Result<ProcessNetwork, LaunchError> {
+        validate_config(config)?;
+        self.launch(&Topology::from_config(config))
+    }
+
+    /// Start a process for every drone of the topology and link them.
+    /// Clients and servers are endpoints in this process, only collecting their packets.
+    /// # Errors
+    /// If a drone cannot be started or does not connect in time.
+    pub fn launch(&self, topology: &Topology) -> Result<ProcessNetwork, LaunchError> {
+        fs::create_dir_all(&self.log_dir)?;
+        let control = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        let (event_send, event_recv) = unbounded();
+
+        let mut network = ProcessNetwork {
+            launcher: self.clone(),
+            control_address: control.local_addr()?,
+            control,
+            topology: topology.clone(),
+            drones: BTreeMap::new(),
+            endpoints: BTreeMap::new(),
+            addresses: HashMap::new(),
+            transport: UdpTransport::new(),
+            packet_send: Arc::default(),
+            event_send,
+            event_recv,
+        };
+
+        let mut spawned = vec![];
+        for (id, kind) in topology.nodes() {
+            if let NodeKind::Drone { pdr } = kind {
+                let process = DroneProcess {
+                    id,
+                    pdr,
+                    listen: SocketAddr::from(([127, 0, 0, 1], 0)),
+                    control: network.control_address,
+                    seed: self.seed,
+                    drain: self.drain,
+                };
+                File::create(network.log_path(id))?;
+                network.spawn(process)?;
+                spawned.push(id);
+            } else {
+                let (address, packet_recv) =
+                    network.transport.listen(&([127, 0, 0, 1], 0).into())?;
+                network.addresses.insert(id, address);
+                network.endpoints.insert(id, packet_recv);
+                let sender = network.transport.connect(&address)?;
+                network.senders().insert(id, sender);
+            }
+        }
+
+        network.accept_pending(&spawned)?;
+        for id in topology.nodes().map(|(id, _)| id).collect::<Vec<_>>() {
+            if network.drones.contains_key(&id) {
+                network.link_neighbors(id)?;
+            }
+        }
+        Ok(network)
+    }
+}
+
+/// A drone child process.
+struct DroneChild {
+    /// Arguments of the process, listening on the address it got at its first start.
+    process: DroneProcess,
+    child: Child,
+    /// Control socket, `None` until the process connects.
+    control: Option<TcpStream>,
+    /// Notified when the drone answers a [`RemoteCommand::Sync`].
+    synced_recv: Receiver<()>,
+    /// The drone was told to crash, it is not restarted by [`ProcessNetwork::restart_exited`].
+    crashed: bool,
+}
+
+/// Channels to send packets to each node, by id.
+type Senders = Arc<Mutex<HashMap<NodeId, Sender<Packet>>>>;
+
+/// A network whose drones run as child processes, exchanging packets over UDP on localhost.
+/// The launcher acts as Simulation Controller, delivering the packets sent through the shortcut,
+/// and as every client and server.
+/// Every drone process is killed when the network is dropped.
+pub struct ProcessNetwork {
+    launcher: Launcher,
+    control: TcpListener,
+    control_address: SocketAddr,
+    topology: Topology,
+    drones: BTreeMap<NodeId, DroneChild>,
+    /// Packets received by every client and server.
+    endpoints: BTreeMap<NodeId, Receiver<Packet>>,
+    /// Where every node receives packets.
+    addresses: HashMap<NodeId, SocketAddr>,
+    /// Transport of the clients and servers.
+    transport: UdpTransport,
+    /// Channel to send packets to each node, shared with the threads delivering shortcuts.
+    packet_send: Senders,
+    event_send: Sender<(NodeId, DroneEvent)>,
+    event_recv: Receiver<(NodeId, DroneEvent)>,
+}
+
+impl ProcessNetwork {
+    /// Current topology, updated by the operations on the network.
+    #[must_use]
+    pub fn topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    /// Ids of all the drones.
+    pub fn drone_ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.drones.keys().copied()
+    }
+
+    /// Address the node receives packets on.
+    #[must_use]
+    pub fn address(&self, id: NodeId) -> Option<SocketAddr> {
+        self.addresses.get(&id).copied()
+    }
+
+    /// Channel of the packets received by a client or server.
+    #[must_use]
+    pub fn packet_recv(&self, id: NodeId) -> Option<&Receiver<Packet>> {
+        self.endpoints.get(&id)
+    }
+
+    /// File with the output of every run of the drone process.
+    #[must_use]
+    pub fn log_path(&self, id: NodeId) -> PathBuf {
+        self.launcher.log_dir.join(format!("drone_{id}.log"))
+    }
+
+    /// Output of every run of the drone process, with the actions of the launcher on it.
+    /// # Errors
+    /// If the log cannot be read.
+    pub fn logs(&self, id: NodeId) -> io::Result<String> {
+        fs::read_to_string(self.log_path(id))
+    }
+
+    /// If the drone process did not exit yet.
+    /// # Errors
+    /// If the node is not a drone or its status cannot be read.
+    pub fn is_running(&mut self, id: NodeId) -> Result<bool, LaunchError> {
+        Ok(self.drone_mut(id)?.child.try_wait()?.is_none())
+    }
+
+    /// Wait for the drone process to exit.
+    /// # Returns
+    /// Its exit status, `None` on timeout.
+    /// # Errors
+    /// If the node is not a drone or its status cannot be read.
+    pub fn wait(
+        &mut self,
+        id: NodeId,
+        timeout: Duration,
+    ) -> Result<Option<ExitStatus>, LaunchError> {
+        let deadline = Instant::now() + timeout;
+        let drone = self.drone_mut(id)?;
+        loop {
+            if let Some(status) = drone.child.try_wait()? {
+                return Ok(Some(status));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Kill the drone process without notifying anybody, like a hard crash.
+    /// Its neighbors keep sending packets to it, which are lost until the host reports
+    /// that nobody listens anymore: then they answer with an `ErrorInRouting` nack.
+    /// # Errors
+    /// If the node is not a drone or the process cannot be killed.
+    pub fn kill(&mut self, id: NodeId) -> Result<(), LaunchError> {
+        let drone = self.drone_mut(id)?;
+        if drone.child.try_wait()?.is_none() {
+            drone.child.kill()?;
+            drone.child.wait()?;
+        }
+        drone.control = None;
+        self.log(id, "killed")
+    }
+
+    /// Kill the drone process if still running, then start it again on the same address
+    /// and give it its current neighbors. The running drones among them get a new sender
+    /// toward it, the old one may have been closed.
+    /// # Errors
+    /// If the node is not a drone or its process cannot be started.
+    pub fn restart(&mut self, id: NodeId) -> Result<(), LaunchError> {
+        self.kill(id)?;
+        let process = self.drone_mut(id)?.process.clone();
+        self.log(id, "restarting")?;
+        self.spawn(process)?;
+        self.accept_pending(&[id])?;
+        self.link_neighbors(id)?;
+
+        let address = self.address(id).ok_or(SimulationError::UnknownNode(id))?;
+        let neighbors = self.topology.neighbors(id).collect::<Vec<_>>();
+        for neighbor in neighbors {
+            let running = self
+                .drones
+                .get(&neighbor)
+                .is_some_and(|drone| drone.control.is_some() && !drone.crashed);
+            if running {
+                self.command(neighbor, &RemoteCommand::AddSender(id, address))?;
+                self.sync(neighbor)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Restart every drone process that exited without being crashed through
+    /// [`ProcessNetwork::crash`].
+    /// # Returns
+    /// The restarted drones.
+    /// # Errors
+    /// If a drone cannot be restarted.
+    pub fn restart_exited(&mut self) -> Result<Vec<NodeId>, LaunchError> {
+        let mut exited = vec![];
+        for (id, drone) in &mut self.drones {
+            if !drone.crashed && drone.child.try_wait()?.is_some() {
+                exited.push(*id);
+            }
+        }
+        for id in &exited {
+            self.log(*id, "exited")?;
+            self.restart(*id)?;
+        }
+        Ok(exited)
+    }
+
+    /// Crash a drone as the protocol requires: its neighbors remove it, then it is told to crash.
+    /// Its process exits once it drained its queued packets.
+    /// # Errors
+    /// If the node is not a running drone.
+    pub fn crash(&mut self, id: NodeId) -> Result<(), LaunchError> {
+        if self.drone_mut(id)?.crashed {
+            return Err(SimulationError::Stopped(id).into());
+        }
+
+        let neighbors = self.topology.neighbors(id).collect::<Vec<_>>();
+        for neighbor in neighbors {
+            if self.drones.contains_key(&neighbor) {
+                // The neighbor may have crashed already
+                let _ = self.command(neighbor, &RemoteCommand::RemoveSender(id));
+            }
+            self.topology.disconnect(id, neighbor);
+        }
+
+        self.command(id, &RemoteCommand::Crash)?;
+        self.drone_mut(id)?.crashed = true;
+        Ok(())
+    }
+
+    /// # Errors
+    /// If the node is not a running drone.
+    pub fn set_pdr(&mut self, id: NodeId, pdr: f32) -> Result<(), LaunchError> {
+        self.command(id, &RemoteCommand::SetPacketDropRate(pdr))?;
+        self.sync(id)
+    }
+
+    /// Link two nodes, notifying the drones among them.
+    /// # Errors
+    /// If one of the nodes does not exist or a drone is not running.
+    pub fn add_link(&mut self, a: NodeId, b: NodeId) -> Result<(), LaunchError> {
+        let address_a = self.address(a).ok_or(SimulationError::UnknownNode(a))?;
+        let address_b = self.address(b).ok_or(SimulationError::UnknownNode(b))?;
+
+        if self.drones.contains_key(&a) {
+            self.command(a, &RemoteCommand::AddSender(b, address_b))?;
+            self.sync(a)?;
+        }
+        if self.drones.contains_key(&b) {
+            self.command(b, &RemoteCommand::AddSender(a, address_a))?;
+            self.sync(b)?;
+        }
+        self.topology.connect(a, b);
+        Ok(())
+    }
+
+    /// Remove the link between two nodes, notifying the drones among them.
+    /// # Errors
+    /// If the nodes are not linked or a drone is not running.
+    pub fn remove_link(&mut self, a: NodeId, b: NodeId) -> Result<(), LaunchError> {
+        if !self.topology.are_connected(a, b) {
+            return Err(SimulationError::NotConnected(a, b).into());
+        }
+
+        for (from, to) in [(a, b), (b, a)] {
+            if self.drones.contains_key(&from) {
+                self.command(from, &RemoteCommand::RemoveSender(to))?;
+                self.sync(from)?;
+            }
+        }
+        self.topology.disconnect(a, b);
+        Ok(())
+    }
+
+    /// Send a packet from a client or server to the next hop of its routing header.
+    /// The routing header must already point to the next hop (as if it was forwarded).
+    /// # Errors
+    /// If the node is not an endpoint or it is not linked to the next hop.
+    pub fn send_from(&self, from: NodeId, packet: Packet) -> Result<(), LaunchError> {
+        if !self.endpoints.contains_key(&from) {
+            return Err(SimulationError::NotAnEndpoint(from).into());
+        }
+
+        let next = packet
+            .routing_header
+            .current_hop()
+            .ok_or(SimulationError::NoNextHop)?;
+        if !self.topology.are_connected(from, next) {
+            return Err(SimulationError::NotConnected(from, next).into());
+        }
+        if !self.drones.contains_key(&next) {
+            return Err(SimulationError::NotADrone(next).into());
+        }
+        let sender = self
+            .senders()
+            .get(&next)
+            .cloned()
+            .ok_or(SimulationError::Stopped(next))?;
+        sender
+            .send(packet)
+            .map_err(|_| SimulationError::Stopped(next).into())
+    }
+
+    /// Wait for the next event sent by any drone.
+    /// # Returns
+    /// The drone that sent the event and the event, `None` on timeout.
+    #[must_use]
+    pub fn next_event(&self, timeout: Duration) -> Option<(NodeId, DroneEvent)> {
+        self.event_recv.recv_timeout(timeout).ok()
+    }
+
+    fn senders(&self) -> MutexGuard<'_, HashMap<NodeId, Sender<Packet>>> {
+        self.packet_send
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn drone_mut(&mut self, id: NodeId) -> Result<&mut DroneChild, SimulationError> {
+        match self.topology.kind(id) {
+            None => Err(SimulationError::UnknownNode(id)),
+            Some(NodeKind::Drone { .. }) => self
+                .drones
+                .get_mut(&id)
+                .ok_or(SimulationError::UnknownNode(id)),
+            Some(_) => Err(SimulationError::NotADrone(id)),
+        }
+    }
+
+    /// Start the process of a drone, it is connected by [`ProcessNetwork::accept_pending`].
+    fn spawn(&mut self, process: DroneProcess) -> Result<(), LaunchError> {
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.log_path(process.id))?;
+        let child = Command::new(&self.launcher.program)
+            .args(process.to_args())
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .spawn()?;
+
+        self.drones.insert(
+            process.id,
+            DroneChild {
+                process,
+                child,
+                control: None,
+                synced_recv: unbounded().1,
+                crashed: false,
+            },
+        );
+        Ok(())
+    }
+
+    /// Wait until the drone processes just started connected.
+    /// Killed drones are not connected either, they are not waited for.
+    fn accept_pending(&mut self, spawned: &[NodeId]) -> Result<(), LaunchError> {
+        let deadline = Instant::now() + self.launcher.connect_timeout;
+        self.control.set_nonblocking(true)?;
+
+        loop {
+            let mut pending = None;
+            for id in spawned {
+                let drone = self.drone_mut(*id)?;
+                if drone.control.is_none() {
+                    if let Some(status) = drone.child.try_wait()? {
+                        return Err(LaunchError::Exited(*id, status));
+                    }
+                    pending = pending.or(Some(*id));
+                }
+            }
+            let Some(pending) = pending else {
+                return Ok(());
+            };
+
+            match self.control.accept() {
+                Ok((stream, _)) => self.connect(stream, deadline)?,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Err(LaunchError::Timeout(pending));
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Read the hello of a new control connection and start receiving its reports.
+    fn connect(&mut self, mut stream: TcpStream, deadline: Instant) -> Result<(), LaunchError> {
+        stream.set_nonblocking(false)?;
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        stream.set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+        let Ok(Some(Report::Hello(id, address))) = read_report(&mut stream) else {
+            // Not a drone we started
+            return Ok(());
+        };
+        stream.set_read_timeout(None)?;
+
+        let Some(drone) = self.drones.get_mut(&id).filter(|d| d.control.is_none()) else {
+            return Ok(());
+        };
+        let (synced_send, synced_recv) = unbounded();
+        let event_send = self.event_send.clone();
+        let packet_send = self.packet_send.clone();
+        let reader = stream.try_clone()?;
+        thread::spawn(move || {
+            receive_reports(id, reader, &event_send, &synced_send, &packet_send);
+        });
+
+        drone.process.listen = address;
+        drone.control = Some(stream);
+        drone.synced_recv = synced_recv;
+        self.addresses.insert(id, address);
+        let sender = self.transport.connect(&address)?;
+        self.senders().insert(id, sender);
+        Ok(())
+    }
+
+    /// Give the drone a sender toward each of its neighbors.
+    fn link_neighbors(&mut self, id: NodeId) -> Result<(), LaunchError> {
+        let neighbors = self.topology.neighbors(id).collect::<Vec<_>>();
+        for neighbor in neighbors {
+            if let Some(address) = self.address(neighbor) {
+                self.command(id, &RemoteCommand::AddSender(neighbor, address))?;
+            }
+        }
+        self.sync(id)
+    }
+
+    fn command(&mut self, id: NodeId, command: &RemoteCommand) -> Result<(), LaunchError> {
+        let drone = self.drone_mut(id)?;
+        let control = drone
+            .control
+            .as_mut()
+            .filter(|_| !drone.crashed)
+            .ok_or(SimulationError::Stopped(id))?;
+        write_command(control, command).map_err(|_| SimulationError::Stopped(id).into())
+    }
+
+    /// Wait until the drone received the previous commands, so that they are handled
+    /// before any packet sent from now on.
+    fn sync(&mut self, id: NodeId) -> Result<(), LaunchError> {
+        self.command(id, &RemoteCommand::Sync)?;
+        let timeout = self.launcher.connect_timeout;
+        self.drone_mut(id)?
+            .synced_recv
+            .recv_timeout(timeout)
+            .map_err(|_| SimulationError::Stopped(id).into())
+    }
+
+    /// Write an action of the launcher in the log of the drone.
+    fn log(&self, id: NodeId, action: &str) -> Result<(), LaunchError> {
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.log_path(id))?;
+        writeln!(log, "launcher: drone {id} {action}")?;
+        Ok(())
+    }
+}
+
+impl Drop for ProcessNetwork {
+    fn drop(&mut self) {
+        for drone in self.drones.values_mut() {
+            let _ = drone.child.kill();
+            let _ = drone.child.wait();
+        }
+        for id in self.endpoints.keys() {
+            let _ = self.transport.close(&self.addresses[id]);
+        }
+    }
+}
+
+/// Forward the reports of a drone process until its control socket is closed, delivering
+/// the packets sent through the shortcut.
+fn receive_reports(
+    id: NodeId,
+    mut stream: TcpStream,
+    event_send: &Sender<(NodeId, DroneEvent)>,
+    synced_send: &Sender<()>,
+    packet_send: &Senders,
+) {
+    while let Ok(Some(report)) = read_report(&mut stream) {
+        match report {
+            Report::Event(event) => {
+                if let DroneEvent::ControllerShortcut(ref packet) = event {
+                    deliver_shortcut(packet_send, packet.clone());
+                }
+                if event_send.send((id, event)).is_err() {
+                    return;
+                }
+            }
+            Report::Synced => {
+                let _ = synced_send.send(());
+            }
+            Report::Hello(..) => {}
+        }
+    }
+}
+
+/// Send a packet to the last node of its route, like
+/// [`Network::deliver_shortcut`](crate::simulation::Network::deliver_shortcut) does.
+/// The packet is lost if the node is unknown.
+fn deliver_shortcut(packet_send: &Senders, packet: Packet) {
+    let Some(destination) = packet.routing_header.hops.last() else {
+        return;
+    };
+    let sender = packet_send
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(destination)
+        .cloned();
+    if let Some(sender) = sender {
+        let _ = sender.send(packet);
+    }
+}
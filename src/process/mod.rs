@@ -0,0 +1,13 @@
+//! Drones running as separate processes, started and supervised by a [`Launcher`].
+//!
+//! Each drone process runs a [`DroneProcess`] (see the `rusty-drone` binary): packets travel
+//! over UDP on localhost and commands and events over a control socket to the launcher.
+//! Unlike threads, a drone process can be killed abruptly, without a `Crash` command.
+
+mod control;
+mod launcher;
+mod node;
+mod test;
+
+pub use launcher::{LaunchError, Launcher, ProcessNetwork};
+pub use node::DroneProcess;
@@ -0,0 +1,207 @@
+use crate::drone::{DrainPolicy, DroneExit, ExitReason, RustyDrone};
+use crate::process::control::{read_command, write_report, RemoteCommand, Report};
+use crate::transport::{Transport, UdpTransport};
+use crossbeam_channel::{never, select, unbounded, Receiver, Sender};
+use std::io;
+use std::io::BufWriter;
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+
+/// A [`RustyDrone`] running as its own process: packets travel over UDP and the
+/// Simulation Controller is the launcher, reached through a TCP socket on localhost.
+/// The neighbors are given by the launcher once the drone is connected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroneProcess {
+    pub id: NodeId,
+    pub pdr: f32,
+    /// Address to receive packets on, the port can be 0 to let the system choose.
+    pub listen: SocketAddr,
+    /// Address of the launcher control socket.
+    pub control: SocketAddr,
+    /// Simulation seed, the drone seed comes from the system if `None`.
+    pub seed: Option<u64>,
+    /// How long a crashed drone keeps handling its queued packets.
+    pub drain: Duration,
+}
+
+impl DroneProcess {
+    /// Parse the command line arguments, as written by [`DroneProcess::to_args`].
+    /// # Errors
+    /// A message describing the missing or invalid argument.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut id = None;
+        let mut pdr = 0.0;
+        let mut listen = None;
+        let mut control = None;
+        let mut seed = None;
+        let mut drain = Duration::from_millis(100);
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for `{arg}`"));
+            match arg.as_str() {
+                "--id" => id = Some(value()?.parse().map_err(|_| "invalid id")?),
+                "--pdr" => pdr = value()?.parse().map_err(|_| "invalid pdr")?,
+                "--listen" => listen = Some(value()?.parse().map_err(|_| "invalid address")?),
+                "--control" => control = Some(value()?.parse().map_err(|_| "invalid address")?),
+                "--seed" => seed = Some(value()?.parse().map_err(|_| "invalid seed")?),
+                "--drain" => {
+                    drain = Duration::from_millis(value()?.parse().map_err(|_| "invalid drain")?);
+                }
+                _ => return Err(format!("unknown argument `{arg}`")),
+            }
+        }
+
+        Ok(Self {
+            id: id.ok_or("missing `--id`")?,
+            pdr,
+            listen: listen.unwrap_or_else(|| SocketAddr::from(([127, 0, 0, 1], 0))),
+            control: control.ok_or("missing `--control`")?,
+            seed,
+            drain,
+        })
+    }
+
+    /// Command line arguments to start this drone.
+    #[must_use]
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "--id".to_string(),
+            self.id.to_string(),
+            "--pdr".to_string(),
+            self.pdr.to_string(),
+            "--listen".to_string(),
+            self.listen.to_string(),
+            "--control".to_string(),
+            self.control.to_string(),
+            "--drain".to_string(),
+            self.drain.as_millis().to_string(),
+        ];
+        if let Some(seed) = self.seed {
+            args.extend(["--seed".to_string(), seed.to_string()]);
+        }
+        args
+    }
+
+    /// Connect to the launcher and run the drone until it crashes or the launcher is gone.
+    /// What happens is logged on the standard error.
+    /// # Errors
+    /// If the control socket or the packet socket cannot be opened.
+    pub fn run(&self) -> io::Result<ExitReason> {
+        let control = TcpStream::connect(self.control)?;
+        let transport = UdpTransport::new();
+        let (address, packet_recv) = transport.listen(&self.listen)?;
+        eprintln!("drone {}: listening on {address}", self.id);
+
+        let (controller_send, event_recv) = unbounded();
+        let (command_send, controller_recv) = unbounded();
+        let (report_send, report_recv) = unbounded();
+        let (exit_send, exit_recv) = unbounded();
+        report_send
+            .send(Report::Hello(self.id, address))
+            .map_err(io::Error::other)?;
+
+        let writer = BufWriter::new(control.try_clone()?);
+        let reports = thread::spawn(move || send_reports(writer, report_recv, &event_recv));
+        let id = self.id;
+        let commands = transport.clone();
+        thread::spawn(move || {
+            receive_commands(id, &commands, control, &command_send, &report_send);
+        });
+
+        let mut builder = RustyDrone::builder()
+            .id(self.id)
+            .pdr(self.pdr)
+            .controller_send(controller_send)
+            .controller_recv(controller_recv)
+            .packet_recv(packet_recv)
+            .drain(DrainPolicy::Timeout(self.drain))
+            .exit_send(exit_send);
+        if let Some(seed) = self.seed {
+            builder = builder.simulation_seed(seed);
+        }
+        let mut drone = builder
+            .build()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        drone.run();
+        // Let the last events reach the launcher
+        drop(drone);
+        let _ = reports.join();
+        transport.close(&address)?;
+
+        let reason = exit_recv
+            .try_recv()
+            .map_or(ExitReason::Disconnected, |DroneExit { reason, .. }| reason);
+        eprintln!("drone {}: stopped, {reason:?}", self.id);
+        Ok(reason)
+    }
+}
+
+/// Write the reports and the drone events to the launcher until the drone is dropped.
+fn send_reports(
+    mut writer: BufWriter<TcpStream>,
+    mut report_recv: Receiver<Report>,
+    event_recv: &Receiver<DroneEvent>,
+) {
+    loop {
+        let report = select! {
+            recv(report_recv) -> report => report.ok(),
+            recv(event_recv) -> event => match event {
+                Ok(event) => Some(Report::Event(event)),
+                Err(_) => return,
+            },
+        };
+        let Some(report) = report else {
+            report_recv = never();
+            continue;
+        };
+        if write_report(&mut writer, &report).is_err() {
+            return;
+        }
+    }
+}
+
+/// Hand the commands of the launcher to the drone until the socket is closed.
+fn receive_commands(
+    id: NodeId,
+    transport: &UdpTransport,
+    mut control: TcpStream,
+    command_send: &Sender<DroneCommand>,
+    report_send: &Sender<Report>,
+) {
+    loop {
+        let command = match read_command(&mut control) {
+            Ok(Some(command)) => command,
+            Ok(None) => return,
+            Err(err) => {
+                eprintln!("drone {id}: invalid command, {err}");
+                return;
+            }
+        };
+        eprintln!("drone {id}: {command:?}");
+
+        let command = match command {
+            RemoteCommand::AddSender(neighbor, address) => match transport.connect(&address) {
+                Ok(sender) => DroneCommand::AddSender(neighbor, sender),
+                Err(err) => {
+                    eprintln!("drone {id}: cannot reach {neighbor} at {address}, {err}");
+                    continue;
+                }
+            },
+            RemoteCommand::RemoveSender(neighbor) => DroneCommand::RemoveSender(neighbor),
+            RemoteCommand::SetPacketDropRate(pdr) => DroneCommand::SetPacketDropRate(pdr),
+            RemoteCommand::Crash => DroneCommand::Crash,
+            RemoteCommand::Sync => {
+                let _ = report_send.send(Report::Synced);
+                continue;
+            }
+        };
+        if command_send.send(command).is_err() {
+            return;
+        }
+    }
+}
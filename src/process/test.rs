@@ -0,0 +1,88 @@
+#![cfg(test)]
+
+use crate::process::control::{
+    read_command, read_report, write_command, write_report, RemoteCommand, Report,
+};
+use crate::process::DroneProcess;
+use rusty_tester::utils::data::new_test_fragment_packet;
+use std::time::Duration;
+use wg_2024::controller::DroneEvent;
+
+#[test]
+fn test_process_args() {
+    let process = DroneProcess {
+        id: 11,
+        pdr: 0.25,
+        listen: "127.0.0.1:9011".parse().unwrap(),
+        control: "127.0.0.1:9000".parse().unwrap(),
+        seed: Some(7),
+        drain: Duration::from_millis(50),
+    };
+    assert_eq!(DroneProcess::from_args(process.to_args()), Ok(process));
+
+    let args = ["--id", "3", "--control", "[::1]:80"].map(String::from);
+    let process = DroneProcess::from_args(args).unwrap();
+    assert_eq!(process.listen, "127.0.0.1:0".parse().unwrap());
+    assert_eq!(process.seed, None);
+
+    let args = ["--control", "127.0.0.1:80"].map(String::from);
+    assert_eq!(
+        DroneProcess::from_args(args),
+        Err("missing `--id`".to_string())
+    );
+    let args = ["--id", "3", "--pdr"].map(String::from);
+    assert_eq!(
+        DroneProcess::from_args(args),
+        Err("missing value for `--pdr`".to_string())
+    );
+}
+
+#[test]
+fn test_process_control_messages() {
+    let commands = [
+        RemoteCommand::AddSender(12, "127.0.0.1:9012".parse().unwrap()),
+        RemoteCommand::AddSender(13, "[::1]:9013".parse().unwrap()),
+        RemoteCommand::RemoveSender(12),
+        RemoteCommand::SetPacketDropRate(0.5),
+        RemoteCommand::Crash,
+        RemoteCommand::Sync,
+    ];
+    let mut bytes = vec![];
+    for command in &commands {
+        write_command(&mut bytes, command).unwrap();
+    }
+    let mut reader = &bytes[..];
+    for command in commands {
+        assert_eq!(read_command(&mut reader).unwrap(), Some(command));
+    }
+    assert_eq!(read_command(&mut reader).unwrap(), None);
+
+    let packet = new_test_fragment_packet(&[1, 11, 12], 5);
+    let mut bytes = vec![];
+    write_report(
+        &mut bytes,
+        &Report::Hello(11, "127.0.0.1:9011".parse().unwrap()),
+    )
+    .unwrap();
+    write_report(
+        &mut bytes,
+        &Report::Event(DroneEvent::PacketDropped(packet.clone())),
+    )
+    .unwrap();
+    write_report(&mut bytes, &Report::Synced).unwrap();
+    let mut reader = &bytes[..];
+    assert!(matches!(
+        read_report(&mut reader),
+        Ok(Some(Report::Hello(11, address))) if address.port() == 9011
+    ));
+    assert!(matches!(
+        read_report(&mut reader),
+        Ok(Some(Report::Event(DroneEvent::PacketDropped(p)))) if p == packet
+    ));
+    assert!(matches!(read_report(&mut reader), Ok(Some(Report::Synced))));
+    assert!(matches!(read_report(&mut reader), Ok(None)));
+
+    // Unknown tag, then a message cut short
+    assert!(read_command(&mut &[1, 0, 0, 0, 9][..]).is_err());
+    assert!(read_command(&mut &[2, 0, 0, 0, 1][..]).is_err());
+}
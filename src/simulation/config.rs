@@ -0,0 +1,261 @@
+use crate::simulation::{Network, NodeKind, SimulationError, Topology};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::{fs, io};
+use wg_2024::config::Config;
+use wg_2024::network::NodeId;
+
+/// Reason why a network initialization file cannot be used.
+#[derive(Debug)]
+pub enum NetworkConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    /// More than one node uses this id.
+    DuplicateId(NodeId),
+    /// The node lists itself as neighbor.
+    SelfLoop(NodeId),
+    /// The node lists the same neighbor more than once.
+    DuplicateNeighbor {
+        node: NodeId,
+        neighbor: NodeId,
+    },
+    /// The packet drop rate of a drone is not between 0 and 1.
+    InvalidPdr {
+        drone: NodeId,
+        pdr: f32,
+    },
+    /// The node lists a neighbor that does not exist.
+    UnknownNeighbor {
+        node: NodeId,
+        neighbor: NodeId,
+    },
+    /// The node lists a neighbor that does not list it back.
+    NotBidirectional {
+        node: NodeId,
+        neighbor: NodeId,
+    },
+    /// A client or server lists a neighbor that is not a drone.
+    NotConnectedToDrone {
+        node: NodeId,
+        neighbor: NodeId,
+    },
+    /// A client must be connected to one or two drones.
+    ClientConnections {
+        client: NodeId,
+        count: usize,
+    },
+    /// A server must be connected to at least two drones.
+    ServerConnections {
+        server: NodeId,
+        count: usize,
+    },
+    /// The network could not be started.
+    Spawn(SimulationError),
+}
+
+impl Display for NetworkConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkConfigError::Io(err) => write!(f, "cannot read network file: {err}"),
+            NetworkConfigError::Parse(err) => write!(f, "invalid network file: {err}"),
+            NetworkConfigError::DuplicateId(id) => write!(f, "node id {id} is used more than once"),
+            NetworkConfigError::SelfLoop(id) => write!(f, "node {id} is connected to itself"),
+            NetworkConfigError::DuplicateNeighbor { node, neighbor } => {
+                write!(f, "node {node} lists {neighbor} more than once")
+            }
+            NetworkConfigError::InvalidPdr { drone, pdr } => {
+                write!(f, "drone {drone} has pdr {pdr}, not between 0 and 1")
+            }
+            NetworkConfigError::UnknownNeighbor { node, neighbor } => {
+                write!(f, "node {node} is connected to unknown node {neighbor}")
+            }
+            NetworkConfigError::NotBidirectional { node, neighbor } => {
+                write!(
+                    f,
+                    "node {node} lists {neighbor}, but {neighbor} does not list {node}"
+                )
+            }
+            NetworkConfigError::NotConnectedToDrone { node, neighbor } => {
+                write!(
+                    f,
+                    "node {node} can only be connected to drones, not to {neighbor}"
+                )
+            }
+            NetworkConfigError::ClientConnections { client, count } => {
+                write!(
+                    f,
+                    "client {client} is connected to {count} drones instead of 1 or 2"
+                )
+            }
+            NetworkConfigError::ServerConnections { server, count } => {
+                write!(
+                    f,
+                    "server {server} is connected to {count} drones instead of at least 2"
+                )
+            }
+            NetworkConfigError::Spawn(err) => write!(f, "cannot start network: {err}"),
+        }
+    }
+}
+
+impl Error for NetworkConfigError {}
+
+impl From<io::Error> for NetworkConfigError {
+    fn from(err: io::Error) -> Self {
+        NetworkConfigError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for NetworkConfigError {
+    fn from(err: toml::de::Error) -> Self {
+        NetworkConfigError::Parse(err)
+    }
+}
+
+impl From<SimulationError> for NetworkConfigError {
+    fn from(err: SimulationError) -> Self {
+        NetworkConfigError::Spawn(err)
+    }
+}
+
+/// Read and validate a network initialization file.
+/// # Errors
+/// If the file cannot be read, parsed or does not describe a valid network.
+pub fn load_config(path: impl AsRef<Path>) -> Result<Config, NetworkConfigError> {
+    parse_config(&fs::read_to_string(path)?)
+}
+
+/// Parse and validate the content of a network initialization file.
+/// # Errors
+/// If the text cannot be parsed or does not describe a valid network.
+pub fn parse_config(text: &str) -> Result<Config, NetworkConfigError> {
+    let config = toml::from_str(text)?;
+    validate_config(&config)?;
+    Ok(config)
+}
+
+/// Check the rules of the network initialization file.
+/// # Errors
+/// The first rule violated.
+pub fn validate_config(config: &Config) -> Result<(), NetworkConfigError> {
+    let mut nodes = BTreeMap::new();
+    let mut neighbors = BTreeMap::new();
+
+    let drones = config
+        .drone
+        .iter()
+        .map(|d| (d.id, NodeKind::Drone { pdr: d.pdr }, &d.connected_node_ids));
+    let clients = config
+        .client
+        .iter()
+        .map(|c| (c.id, NodeKind::Client, &c.connected_drone_ids));
+    let servers = config
+        .server
+        .iter()
+        .map(|s| (s.id, NodeKind::Server, &s.connected_drone_ids));
+
+    for (id, kind, connected) in drones.chain(clients).chain(servers) {
+        if nodes.insert(id, kind).is_some() {
+            return Err(NetworkConfigError::DuplicateId(id));
+        }
+        if let NodeKind::Drone { pdr } = kind {
+            if !(0.0..=1.0).contains(&pdr) {
+                return Err(NetworkConfigError::InvalidPdr { drone: id, pdr });
+            }
+        }
+
+        let mut unique = BTreeSet::new();
+        for &neighbor in connected {
+            if !unique.insert(neighbor) {
+                return Err(NetworkConfigError::DuplicateNeighbor { node: id, neighbor });
+            }
+        }
+        neighbors.insert(id, unique);
+    }
+
+    for (&node, connected) in &neighbors {
+        for &neighbor in connected {
+            if node == neighbor {
+                return Err(NetworkConfigError::SelfLoop(node));
+            }
+            let Some(neighbor_kind) = nodes.get(&neighbor) else {
+                return Err(NetworkConfigError::UnknownNeighbor { node, neighbor });
+            };
+            if !neighbors[&neighbor].contains(&node) {
+                return Err(NetworkConfigError::NotBidirectional { node, neighbor });
+            }
+
+            let endpoint = !matches!(nodes[&node], NodeKind::Drone { .. });
+            if endpoint && !matches!(neighbor_kind, NodeKind::Drone { .. }) {
+                return Err(NetworkConfigError::NotConnectedToDrone { node, neighbor });
+            }
+        }
+
+        let count = connected.len();
+        match nodes[&node] {
+            NodeKind::Client if !(1..=2).contains(&count) => {
+                return Err(NetworkConfigError::ClientConnections {
+                    client: node,
+                    count,
+                });
+            }
+            NodeKind::Server if count < 2 => {
+                return Err(NetworkConfigError::ServerConnections {
+                    server: node,
+                    count,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+impl Topology {
+    /// Topology described by a network initialization file (that is not validated).
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let drones = config.drone.iter().map(|d| {
+            (
+                (d.id, NodeKind::Drone { pdr: d.pdr }),
+                (d.id, d.connected_node_ids.clone()),
+            )
+        });
+        let clients = config.client.iter().map(|c| {
+            (
+                (c.id, NodeKind::Client),
+                (c.id, c.connected_drone_ids.clone()),
+            )
+        });
+        let servers = config.server.iter().map(|s| {
+            (
+                (s.id, NodeKind::Server),
+                (s.id, s.connected_drone_ids.clone()),
+            )
+        });
+
+        let (nodes, adjacency): (Vec<_>, Vec<_>) = drones.chain(clients).chain(servers).unzip();
+        Self::from_adjacency(nodes, adjacency)
+    }
+}
+
+impl Network {
+    /// Validate the configuration, then start a [`crate::RustyDrone`] for every drone
+    /// and an [`crate::simulation::Endpoint`] for every client and server.
+    /// # Errors
+    /// If the configuration is not valid or a drone cannot be created.
+    pub fn from_config(config: &Config) -> Result<Self, NetworkConfigError> {
+        validate_config(config)?;
+        Ok(Self::spawn(&Topology::from_config(config))?)
+    }
+
+    /// Load a network initialization file and start the network it describes.
+    /// # Errors
+    /// See [`load_config`] and [`Network::from_config`].
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, NetworkConfigError> {
+        Self::from_config(&load_config(path)?)
+    }
+}
@@ -0,0 +1,325 @@
+use crate::simulation::{Action, Network, Recorder, Script, SimulationError};
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, Packet, PacketType, FRAGMENT_DSIZE};
+
+/// Maximum time waited for a single event, so that endpoints are checked regularly.
+const POLL: Duration = Duration::from_millis(5);
+
+/// Event sent by a drone, with the time it was received by the controller.
+#[derive(Debug, Clone)]
+pub struct LoggedEvent {
+    /// Time since the start of the simulation.
+    pub at: Duration,
+    pub drone: NodeId,
+    pub event: DroneEvent,
+}
+
+impl Display for LoggedEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (name, packet) = match &self.event {
+            DroneEvent::PacketSent(packet) => ("PacketSent", packet),
+            DroneEvent::PacketDropped(packet) => ("PacketDropped", packet),
+            DroneEvent::ControllerShortcut(packet) => ("ControllerShortcut", packet),
+        };
+        write!(
+            f,
+            "{:>10.3} ms  drone {:<3}  {name:<18}  {}",
+            self.at.as_secs_f64() * 1000.0,
+            self.drone,
+            describe_packet(packet)
+        )
+    }
+}
+
+/// Short, single line description of a packet, without its data.
+#[must_use]
+pub fn describe_packet(packet: &Packet) -> String {
+    let content = match &packet.pack_type {
+        PacketType::MsgFragment(fragment) => format!(
+            "MsgFragment {}/{}",
+            fragment.fragment_index, fragment.total_n_fragments
+        ),
+        PacketType::Ack(ack) => format!("Ack {}", ack.fragment_index),
+        PacketType::Nack(nack) => format!("Nack {} {:?}", nack.fragment_index, nack.nack_type),
+        PacketType::FloodRequest(flood) => format!(
+            "FloodRequest {} from {} path {:?}",
+            flood.flood_id, flood.initiator_id, flood.path_trace
+        ),
+        PacketType::FloodResponse(flood) => {
+            format!(
+                "FloodResponse {} path {:?}",
+                flood.flood_id, flood.path_trace
+            )
+        }
+    };
+    format!(
+        "session {} {content} hops {:?} at {}",
+        packet.session_id, packet.routing_header.hops, packet.routing_header.hop_index
+    )
+}
+
+/// Totals of a simulation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Summary {
+    /// Fragments sent by the endpoints.
+    pub fragments_sent: u64,
+    /// Fragments that reached their destination.
+    pub fragments_delivered: u64,
+    /// Fragments never sent, because no path to the destination existed.
+    pub fragments_unroutable: u64,
+    /// `PacketDropped` events.
+    pub drops: u64,
+    /// Nacks created by the drones.
+    pub nacks: u64,
+    /// `ControllerShortcut` events.
+    pub shortcuts: u64,
+}
+
+impl Summary {
+    /// Fraction of the fragments sent that reached their destination.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn delivery_ratio(&self) -> f64 {
+        if self.fragments_sent == 0 {
+            return 0.0;
+        }
+        self.fragments_delivered as f64 / self.fragments_sent as f64
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "fragments sent:       {}", self.fragments_sent)?;
+        writeln!(f, "fragments delivered:  {}", self.fragments_delivered)?;
+        writeln!(f, "fragments unroutable: {}", self.fragments_unroutable)?;
+        writeln!(
+            f,
+            "delivery ratio:       {:.1}%",
+            self.delivery_ratio() * 100.0
+        )?;
+        writeln!(f, "drops:                {}", self.drops)?;
+        writeln!(f, "nacks:                {}", self.nacks)?;
+        write!(f, "shortcuts:            {}", self.shortcuts)
+    }
+}
+
+/// Headless Simulation Controller: runs a [`Script`] on a [`Network`],
+/// delivering shortcuts and recording every event.
+pub struct Controller {
+    network: Network,
+    start: Instant,
+    events: Vec<LoggedEvent>,
+    /// Packets received by the endpoints, with the endpoint id.
+    received: Vec<(NodeId, Packet)>,
+    summary: Summary,
+    next_session: u64,
+    recorder: Option<Recorder>,
+}
+
+impl Controller {
+    /// Take control of the network, the simulation time starts now.
+    #[must_use]
+    pub fn new(network: Network) -> Self {
+        Self {
+            network,
+            start: Instant::now(),
+            events: vec![],
+            received: vec![],
+            summary: Summary::default(),
+            next_session: 0,
+            recorder: None,
+        }
+    }
+
+    #[must_use]
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Every event received so far.
+    #[must_use]
+    pub fn events(&self) -> &[LoggedEvent] {
+        &self.events
+    }
+
+    /// Every packet received by the endpoints so far, with the endpoint id.
+    #[must_use]
+    pub fn received(&self) -> &[(NodeId, Packet)] {
+        &self.received
+    }
+
+    /// Totals so far, nacks are read from the drone counters.
+    #[must_use]
+    pub fn summary(&self) -> Summary {
+        let nacks = self
+            .network
+            .drone_ids()
+            .filter_map(|id| self.network.stats(id).ok())
+            .map(|stats| stats.nacks.values().sum::<u64>())
+            .sum();
+
+        Summary {
+            nacks,
+            ..self.summary.clone()
+        }
+    }
+
+    /// Write every event to the recorder as soon as it is received,
+    /// starting with the ones received so far. Replaces the previous recorder.
+    pub fn record(&mut self, mut recorder: Recorder) {
+        for event in &self.events {
+            recorder.record(event);
+        }
+        self.recorder = Some(recorder);
+    }
+
+    /// Stop recording, the recorder must still be finished.
+    pub fn take_recorder(&mut self) -> Option<Recorder> {
+        self.recorder.take()
+    }
+
+    /// Run every step at its time, then wait until the network is idle for `settle`.
+    /// # Errors
+    /// If a step cannot be scheduled or applied, the remaining steps are skipped.
+    pub fn run_script(&mut self, script: &Script, settle: Duration) -> Result<(), SimulationError> {
+        for step in script.steps() {
+            let at = self
+                .start
+                .checked_add(step.at)
+                .ok_or(SimulationError::TooLate(step.at))?;
+            self.run_until(at);
+            self.apply(&step.action)?;
+        }
+        self.run_until_idle(settle);
+        Ok(())
+    }
+
+    /// Do an action now.
+    /// # Errors
+    /// If the action refers to unknown nodes or links, or to crashed drones.
+    pub fn apply(&mut self, action: &Action) -> Result<(), SimulationError> {
+        match *action {
+            Action::Crash(id) => self.network.crash(id),
+            Action::SetPdr(id, pdr) => self.network.set_pdr(id, pdr),
+            Action::AddLink(a, b) => self.network.add_link(a, b),
+            Action::RemoveLink(a, b) => self.network.remove_link(a, b),
+            Action::Send {
+                from,
+                to,
+                fragments,
+            } => self.send_message(from, to, fragments),
+        }
+    }
+
+    /// Handle events and endpoint packets until `deadline`.
+    pub fn run_until(&mut self, deadline: Instant) {
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                self.collect_received();
+                return;
+            }
+            self.step((deadline - now).min(POLL));
+        }
+    }
+
+    /// Handle events and endpoint packets until nothing happens for `idle`.
+    pub fn run_until_idle(&mut self, idle: Duration) {
+        let mut last_activity = Instant::now();
+        while last_activity.elapsed() < idle {
+            if self.step(POLL.min(idle)) {
+                last_activity = Instant::now();
+            }
+        }
+    }
+
+    /// Give the network back, for example to join it.
+    #[must_use]
+    pub fn into_network(self) -> Network {
+        self.network
+    }
+
+    /// Wait at most `timeout` for an event, then collect the endpoint packets.
+    /// # Returns
+    /// If anything happened.
+    fn step(&mut self, timeout: Duration) -> bool {
+        let event = self.network.next_event(timeout);
+        let handled = event.is_some();
+        if let Some((drone, event)) = event {
+            self.handle_event(drone, event);
+        }
+        self.collect_received() || handled
+    }
+
+    fn handle_event(&mut self, drone: NodeId, event: DroneEvent) {
+        match event {
+            DroneEvent::PacketDropped(_) => self.summary.drops += 1,
+            DroneEvent::ControllerShortcut(ref packet) => {
+                self.summary.shortcuts += 1;
+                // The destination may have crashed or be unknown, then the packet is lost
+                let _ = self.network.deliver_shortcut(packet.clone());
+            }
+            DroneEvent::PacketSent(_) => {}
+        }
+
+        let logged = LoggedEvent {
+            at: self.start.elapsed(),
+            drone,
+            event,
+        };
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.record(&logged);
+        }
+        self.events.push(logged);
+    }
+
+    /// Move the packets received by the endpoints in `received`.
+    /// # Returns
+    /// If any packet was received.
+    fn collect_received(&mut self) -> bool {
+        let before = self.received.len();
+        for endpoint in self.network.endpoints() {
+            for packet in endpoint.packet_recv().try_iter() {
+                let destination = packet.routing_header.hops.last() == Some(&endpoint.id());
+                if destination && matches!(packet.pack_type, PacketType::MsgFragment(_)) {
+                    self.summary.fragments_delivered += 1;
+                }
+                self.received.push((endpoint.id(), packet));
+            }
+        }
+        self.received.len() > before
+    }
+
+    fn send_message(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        fragments: u64,
+    ) -> Result<(), SimulationError> {
+        let Some(path) = self.network.topology().shortest_path(from, to) else {
+            self.summary.fragments_unroutable += fragments;
+            return Ok(());
+        };
+
+        let session_id = self.next_session;
+        self.next_session += 1;
+        for fragment_index in 0..fragments {
+            let packet = Packet::new_fragment(
+                SourceRoutingHeader::new(path.clone(), 1),
+                session_id,
+                Fragment {
+                    fragment_index,
+                    total_n_fragments: fragments,
+                    length: 0,
+                    data: [0; FRAGMENT_DSIZE],
+                },
+            );
+            self.network.send_from(from, packet)?;
+            self.summary.fragments_sent += 1;
+        }
+        Ok(())
+    }
+}
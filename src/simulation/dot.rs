@@ -0,0 +1,185 @@
+use crate::simulation::{LoggedEvent, Network, NodeKind, Topology};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter, Write};
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{FloodResponse, PacketType};
+
+/// Colors given to the overlays, in order.
+const COLORS: [&str; 8] = [
+    "red",
+    "blue",
+    "darkgreen",
+    "orange",
+    "purple",
+    "brown",
+    "magenta",
+    "cyan4",
+];
+
+/// Path drawn over the graph, like the path trace of a flood or the hops of a fragment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlay {
+    pub label: String,
+    pub color: String,
+    pub path: Vec<NodeId>,
+}
+
+/// Graph of a network that can be written in the Graphviz DOT language.
+#[derive(Debug, Clone, Default)]
+pub struct DotGraph {
+    nodes: BTreeMap<NodeId, Option<NodeKind>>,
+    /// Nodes that can no longer handle packets.
+    crashed: BTreeSet<NodeId>,
+    /// Links between two nodes, the lower id first.
+    links: BTreeSet<(NodeId, NodeId)>,
+    overlays: Vec<Overlay>,
+}
+
+impl DotGraph {
+    /// Graph with the nodes and links of the topology.
+    #[must_use]
+    pub fn from_topology(topology: &Topology) -> Self {
+        let mut graph = Self::default();
+        for (id, kind) in topology.nodes() {
+            graph.nodes.insert(id, Some(kind));
+        }
+        graph.links.extend(topology.edges());
+        graph
+    }
+
+    /// Graph of the current links of a running network, with its crashed drones.
+    #[must_use]
+    pub fn from_network(network: &Network) -> Self {
+        let mut graph = Self::from_topology(network.topology());
+        graph.crashed = network
+            .drone_ids()
+            .filter(|id| network.is_crashed(*id))
+            .collect();
+        graph
+    }
+
+    /// Draw a path with the next color.
+    pub fn overlay(&mut self, label: impl Into<String>, path: Vec<NodeId>) -> &mut Self {
+        let color = COLORS[self.overlays.len() % COLORS.len()].to_string();
+        self.overlays.push(Overlay {
+            label: label.into(),
+            color,
+            path,
+        });
+        self
+    }
+
+    /// Draw the path trace of a flood response.
+    pub fn flood_response(&mut self, response: &FloodResponse) -> &mut Self {
+        let path = response.path_trace.iter().map(|(id, _)| *id).collect();
+        self.overlay(format!("flood {}", response.flood_id), path)
+    }
+
+    /// Draw the hops a fragment went through, from the `PacketSent` events of the drones.
+    /// The sender is an endpoint, so the path starts at the first drone.
+    pub fn journey(
+        &mut self,
+        events: &[LoggedEvent],
+        session_id: u64,
+        fragment_index: u64,
+    ) -> &mut Self {
+        let mut sent = events
+            .iter()
+            .filter_map(|logged| {
+                let DroneEvent::PacketSent(ref packet) = logged.event else {
+                    return None;
+                };
+                let PacketType::MsgFragment(ref fragment) = packet.pack_type else {
+                    return None;
+                };
+                if packet.session_id != session_id || fragment.fragment_index != fragment_index {
+                    return None;
+                }
+                let routing = &packet.routing_header;
+                let to = *routing.hops.get(routing.hop_index)?;
+                Some((routing.hop_index, logged.drone, to))
+            })
+            .collect::<Vec<_>>();
+        // The events of different drones may be received in any order
+        sent.sort_by_key(|(hop_index, _, _)| *hop_index);
+
+        let mut path = Vec::new();
+        for (_, from, to) in sent {
+            if path.last() != Some(&from) {
+                path.push(from);
+            }
+            path.push(to);
+        }
+        self.overlay(
+            format!("session {session_id} fragment {fragment_index}"),
+            path,
+        )
+    }
+
+    #[must_use]
+    pub fn overlays(&self) -> &[Overlay] {
+        &self.overlays
+    }
+
+    /// The graph in the DOT language.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Display for DotGraph {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "graph network {{")?;
+
+        // Every node of an overlay must exist, even if not part of the network
+        let mut nodes = self.nodes.clone();
+        for overlay in &self.overlays {
+            for id in &overlay.path {
+                nodes.entry(*id).or_insert(None);
+            }
+        }
+
+        for (id, kind) in &nodes {
+            let shape = match kind {
+                Some(NodeKind::Drone { .. }) => "circle",
+                Some(NodeKind::Client) => "box",
+                Some(NodeKind::Server) => "box3d",
+                None => "plaintext",
+            };
+            let style = if self.crashed.contains(id) {
+                ", style=dashed, color=gray"
+            } else {
+                ""
+            };
+            writeln!(f, "    {id} [shape={shape}{style}];")?;
+        }
+
+        for (a, b) in &self.links {
+            writeln!(f, "    {a} -- {b};")?;
+        }
+
+        let mut legend = String::new();
+        for overlay in &self.overlays {
+            for hop in overlay.path.windows(2) {
+                writeln!(
+                    f,
+                    "    {} -- {} [color={}, penwidth=2, dir=forward, constraint=false];",
+                    hop[0], hop[1], overlay.color
+                )?;
+            }
+            write!(legend, "{}: {}\\l", overlay.color, escape(&overlay.label))?;
+        }
+        if !legend.is_empty() {
+            writeln!(f, "    label=\"{legend}\";")?;
+            writeln!(f, "    labelloc=b;")?;
+        }
+
+        write!(f, "}}")
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
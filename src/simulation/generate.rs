@@ -0,0 +1,342 @@
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use wg_2024::config::{Client, Config, Drone, Server};
+use wg_2024::network::NodeId;
+
+/// Shape of the drone part of a generated network.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    /// Every drone linked to the previous and the next one.
+    Ring { drones: usize },
+    /// Drones on a `width` x `height` grid, linked to the adjacent ones (no diagonals).
+    Grid { width: usize, height: usize },
+    /// Drones placed uniformly in the unit square, linked if closer than `radius`.
+    RandomGeometric { drones: usize, radius: f64 },
+    /// Every pair of drones linked with probability `p`.
+    ErdosRenyi { drones: usize, p: f64 },
+    /// Preferential attachment: every new drone links to `m` existing ones,
+    /// chosen with probability proportional to their degree.
+    BarabasiAlbert { drones: usize, m: usize },
+    /// Two chains of `length` drones, with each drone linked to the one beside it.
+    DoubleChain { length: usize },
+}
+
+impl Shape {
+    /// Number of drones, `None` if it does not fit in a `usize`.
+    fn drones(&self) -> Option<usize> {
+        match *self {
+            Shape::Ring { drones }
+            | Shape::RandomGeometric { drones, .. }
+            | Shape::ErdosRenyi { drones, .. }
+            | Shape::BarabasiAlbert { drones, .. } => Some(drones),
+            Shape::Grid { width, height } => width.checked_mul(height),
+            Shape::DoubleChain { length } => length.checked_mul(2),
+        }
+    }
+}
+
+/// Reason why a network cannot be generated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenerateError {
+    /// Every node needs a different `NodeId`, so at most 256 nodes fit in a network.
+    TooManyNodes(usize),
+    /// Servers need at least two drones, clients at least one.
+    TooFewDrones(usize),
+    /// A shape parameter is out of its range.
+    InvalidParameter(&'static str),
+}
+
+impl Display for GenerateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerateError::TooManyNodes(n) => write!(f, "{n} nodes do not fit in a NodeId"),
+            GenerateError::TooFewDrones(n) => {
+                write!(f, "{n} drones are not enough for the clients and servers")
+            }
+            GenerateError::InvalidParameter(name) => write!(f, "invalid value for `{name}`"),
+        }
+    }
+}
+
+impl Error for GenerateError {}
+
+/// Generate networks that are valid network initialization files.
+/// Drones get the ids from 0, followed by clients and then servers.
+/// The drones are always connected, random shapes get extra links between their components.
+/// Every client is linked to one or two random drones and every server to two.
+#[derive(Debug, Clone)]
+pub struct NetworkGenerator {
+    shape: Shape,
+    clients: usize,
+    servers: usize,
+    pdr: f32,
+    seed: u64,
+}
+
+impl NetworkGenerator {
+    /// Generator of networks with the given shape, without clients and servers.
+    #[must_use]
+    pub fn new(shape: Shape) -> Self {
+        Self {
+            shape,
+            clients: 0,
+            servers: 0,
+            pdr: 0.0,
+            seed: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn clients(mut self, clients: usize) -> Self {
+        self.clients = clients;
+        self
+    }
+
+    #[must_use]
+    pub fn servers(mut self, servers: usize) -> Self {
+        self.servers = servers;
+        self
+    }
+
+    /// Packet drop rate of every drone.
+    #[must_use]
+    pub fn pdr(mut self, pdr: f32) -> Self {
+        self.pdr = pdr;
+        self
+    }
+
+    /// Seed of random shapes and of the drones chosen for clients and servers.
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// # Errors
+    /// If the nodes do not fit in a `NodeId` or a parameter is invalid.
+    pub fn generate(&self) -> Result<Config, GenerateError> {
+        let drones = self
+            .shape
+            .drones()
+            .ok_or(GenerateError::TooManyNodes(usize::MAX))?;
+        let total = drones
+            .checked_add(self.clients)
+            .and_then(|total| total.checked_add(self.servers))
+            .ok_or(GenerateError::TooManyNodes(usize::MAX))?;
+        if total > usize::from(NodeId::MAX) + 1 {
+            return Err(GenerateError::TooManyNodes(total));
+        }
+        let required = match (self.clients, self.servers) {
+            (_, 1..) => 2,
+            (1.., 0) => 1,
+            (0, 0) => 0,
+        };
+        if drones < required {
+            return Err(GenerateError::TooFewDrones(drones));
+        }
+        if !(0.0..=1.0).contains(&self.pdr) {
+            return Err(GenerateError::InvalidParameter("pdr"));
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut links = vec![BTreeSet::new(); drones];
+        match self.shape {
+            Shape::Ring { drones } => ring(&mut links, drones),
+            Shape::Grid { width, height } => grid(&mut links, width, height),
+            Shape::RandomGeometric { radius, .. } => {
+                if radius.is_nan() || radius < 0.0 {
+                    return Err(GenerateError::InvalidParameter("radius"));
+                }
+                random_geometric(&mut links, radius, &mut rng);
+            }
+            Shape::ErdosRenyi { p, .. } => {
+                if !(0.0..=1.0).contains(&p) {
+                    return Err(GenerateError::InvalidParameter("p"));
+                }
+                erdos_renyi(&mut links, p, &mut rng);
+            }
+            Shape::BarabasiAlbert { drones, m } => {
+                if m == 0 || m >= drones.max(1) {
+                    return Err(GenerateError::InvalidParameter("m"));
+                }
+                barabasi_albert(&mut links, m, &mut rng);
+            }
+            Shape::DoubleChain { length } => double_chain(&mut links, length),
+        }
+        connect_components(&mut links, &mut rng);
+
+        Ok(self.config(links, &mut rng))
+    }
+
+    /// Add clients and servers to the drone links.
+    fn config(&self, mut links: Vec<BTreeSet<usize>>, rng: &mut StdRng) -> Config {
+        let drones = links.len();
+        let all = (0..drones).collect::<Vec<_>>();
+        // Link the node to between `min` and `max` random drones
+        let mut attach = |id: usize, min: usize, max: usize| {
+            let count = rng.random_range(min..=max.min(drones));
+            let chosen = all.choose_multiple(rng, count).copied().collect::<Vec<_>>();
+            for &drone in &chosen {
+                links[drone].insert(id);
+            }
+            chosen.into_iter().map(node_id).collect::<Vec<_>>()
+        };
+
+        let client = (drones..drones + self.clients)
+            .map(|id| Client {
+                id: node_id(id),
+                connected_drone_ids: attach(id, 1, 2),
+            })
+            .collect();
+        let first_server = drones + self.clients;
+        let server = (first_server..first_server + self.servers)
+            .map(|id| Server {
+                id: node_id(id),
+                connected_drone_ids: attach(id, 2, 2),
+            })
+            .collect();
+
+        let drone = links
+            .into_iter()
+            .enumerate()
+            .map(|(id, neighbors)| Drone {
+                id: node_id(id),
+                connected_node_ids: neighbors.into_iter().map(node_id).collect(),
+                pdr: self.pdr,
+            })
+            .collect();
+
+        Config {
+            drone,
+            client,
+            server,
+        }
+    }
+}
+
+/// Generated ids always fit, this is checked before generating.
+#[allow(clippy::cast_possible_truncation)]
+fn node_id(index: usize) -> NodeId {
+    index as NodeId
+}
+
+fn link(links: &mut [BTreeSet<usize>], a: usize, b: usize) {
+    if a != b {
+        links[a].insert(b);
+        links[b].insert(a);
+    }
+}
+
+fn ring(links: &mut [BTreeSet<usize>], drones: usize) {
+    for i in 0..drones {
+        link(links, i, (i + 1) % drones);
+    }
+}
+
+fn grid(links: &mut [BTreeSet<usize>], width: usize, height: usize) {
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            if x + 1 < width {
+                link(links, i, i + 1);
+            }
+            if y + 1 < height {
+                link(links, i, i + width);
+            }
+        }
+    }
+}
+
+fn random_geometric(links: &mut [BTreeSet<usize>], radius: f64, rng: &mut StdRng) {
+    let positions = (0..links.len())
+        .map(|_| (rng.random::<f64>(), rng.random::<f64>()))
+        .collect::<Vec<_>>();
+    for (a, pa) in positions.iter().enumerate() {
+        for (b, pb) in positions.iter().enumerate().skip(a + 1) {
+            if (pa.0 - pb.0).hypot(pa.1 - pb.1) <= radius {
+                link(links, a, b);
+            }
+        }
+    }
+}
+
+fn erdos_renyi(links: &mut [BTreeSet<usize>], p: f64, rng: &mut StdRng) {
+    for a in 0..links.len() {
+        for b in a + 1..links.len() {
+            if rng.random_bool(p) {
+                link(links, a, b);
+            }
+        }
+    }
+}
+
+fn barabasi_albert(links: &mut [BTreeSet<usize>], m: usize, rng: &mut StdRng) {
+    // Every node appears once per link it has, so choosing uniformly is proportional to degree
+    let mut endpoints = vec![];
+    for a in 0..=m {
+        for b in a + 1..=m {
+            link(links, a, b);
+            endpoints.extend([a, b]);
+        }
+    }
+
+    for new in m + 1..links.len() {
+        let mut targets = BTreeSet::new();
+        while targets.len() < m {
+            targets.insert(*endpoints.choose(rng).unwrap_or(&0));
+        }
+        for target in targets {
+            link(links, new, target);
+            endpoints.extend([new, target]);
+        }
+    }
+}
+
+fn double_chain(links: &mut [BTreeSet<usize>], length: usize) {
+    for i in 0..length {
+        link(links, i, length + i);
+        if i + 1 < length {
+            link(links, i, i + 1);
+            link(links, length + i, length + i + 1);
+        }
+    }
+}
+
+/// Link every connected component to a random node of the ones before it.
+fn connect_components(links: &mut [BTreeSet<usize>], rng: &mut StdRng) {
+    let mut component = vec![None; links.len()];
+    let mut components = vec![];
+    for start in 0..links.len() {
+        if component[start].is_some() {
+            continue;
+        }
+
+        let mut members = vec![start];
+        component[start] = Some(components.len());
+        let mut i = 0;
+        while let Some(&node) = members.get(i) {
+            for &neighbor in &links[node] {
+                if component[neighbor].is_none() {
+                    component[neighbor] = Some(components.len());
+                    members.push(neighbor);
+                }
+            }
+            i += 1;
+        }
+        components.push(members);
+    }
+
+    let mut reached = vec![];
+    for mut members in components {
+        if !reached.is_empty() {
+            let a = *members.choose(rng).unwrap_or(&0);
+            let b = *reached.choose(rng).unwrap_or(&0);
+            link(links, a, b);
+        }
+        reached.append(&mut members);
+    }
+}
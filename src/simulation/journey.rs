@@ -0,0 +1,244 @@
+use crate::drone::PacketKind;
+use crate::simulation::{EventKind, EventRecord, RecordedNack};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Write};
+use std::time::Duration;
+use wg_2024::network::NodeId;
+
+/// A drone handing a fragment to the next node of its route.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hop {
+    pub from: NodeId,
+    pub to: NodeId,
+    /// Time since the start of the simulation.
+    pub at: Duration,
+    /// Time since the previous hop, none for the first hop seen.
+    pub elapsed: Option<Duration>,
+}
+
+/// Path of a fragment through the network, as seen from the events of the drones.
+/// The sender is an endpoint, so the first hop is the one of the first drone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Journey {
+    pub session_id: u64,
+    pub fragment_index: u64,
+    /// Route of the routing header, from the sender to the destination.
+    pub route: Vec<NodeId>,
+    /// Every hop seen, in route order.
+    pub hops: Vec<Hop>,
+    /// Drone that dropped the fragment.
+    pub dropped_at: Option<NodeId>,
+    /// Drone that sent back a nack, with its type.
+    pub nacked_at: Option<(NodeId, RecordedNack)>,
+    /// Drones that used the controller shortcut for the ack or nack of the fragment.
+    pub shortcuts: Vec<NodeId>,
+    /// If the fragment was handed to its destination.
+    pub delivered: bool,
+}
+
+impl Journey {
+    fn new(session_id: u64, fragment_index: u64) -> Self {
+        Self {
+            session_id,
+            fragment_index,
+            route: vec![],
+            hops: vec![],
+            dropped_at: None,
+            nacked_at: None,
+            shortcuts: vec![],
+            delivered: false,
+        }
+    }
+
+    /// If the fragment was shortcut on its way or its ack or nack was.
+    #[must_use]
+    pub fn shortcut(&self) -> bool {
+        !self.shortcuts.is_empty()
+    }
+
+    /// Time from the first to the last hop.
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        match (self.hops.first(), self.hops.last()) {
+            (Some(first), Some(last)) => last.at.saturating_sub(first.at),
+            _ => Duration::ZERO,
+        }
+    }
+
+    fn add_fragment_event(&mut self, record: &EventRecord) {
+        let packet = &record.packet;
+        self.route.clone_from(&packet.hops);
+
+        match record.event {
+            EventKind::PacketSent => {
+                let Some(&to) = packet.hops.get(packet.hop_index) else {
+                    return;
+                };
+                self.hops.push(Hop {
+                    from: record.drone,
+                    to,
+                    at: record.at(),
+                    elapsed: None,
+                });
+                if packet.hop_index + 1 == packet.hops.len() {
+                    self.delivered = true;
+                }
+            }
+            EventKind::PacketDropped => self.dropped_at = Some(record.drone),
+            EventKind::ControllerShortcut => self.shortcuts.push(record.drone),
+        }
+    }
+
+    fn add_response_event(&mut self, record: &EventRecord) {
+        let packet = &record.packet;
+        match record.event {
+            EventKind::ControllerShortcut => self.shortcuts.push(record.drone),
+            // The nack is first sent by the drone that created it, at the start of its route
+            _ if self.nacked_at.is_none() && packet.hops.first() == Some(&record.drone) => {
+                if let Some(nack_type) = packet.nack_type {
+                    self.nacked_at = Some((record.drone, nack_type));
+                }
+                // Nacked by the first drone, the route up to it is the one of the nack
+                if self.route.is_empty() {
+                    self.route = packet.hops.iter().rev().copied().collect();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Put the hops in route order, the events of different drones may be received in any order.
+    fn sort_hops(&mut self) {
+        let position = |id: NodeId| self.route.iter().position(|&hop| hop == id);
+        let positions = self
+            .hops
+            .iter()
+            .map(|hop| position(hop.from))
+            .collect::<Vec<_>>();
+
+        let mut hops = positions
+            .into_iter()
+            .zip(self.hops.drain(..))
+            .collect::<Vec<_>>();
+        hops.sort_by_key(|(position, _)| *position);
+        self.hops = hops.into_iter().map(|(_, hop)| hop).collect();
+
+        for i in 1..self.hops.len() {
+            let previous = self.hops[i - 1].at;
+            self.hops[i].elapsed = Some(self.hops[i].at.saturating_sub(previous));
+        }
+    }
+}
+
+impl Display for Journey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        if let Some(first) = self.hops.first() {
+            let mut path = first.from.to_string();
+            for hop in &self.hops {
+                write!(path, " -> {}", hop.to)?;
+                if let Some(elapsed) = hop.elapsed {
+                    write!(path, " (+{:.3} ms)", elapsed.as_secs_f64() * 1000.0)?;
+                }
+            }
+            parts.push(path);
+        }
+
+        if self.delivered {
+            parts.push("delivered".to_string());
+        }
+        if let Some(drone) = self.dropped_at {
+            parts.push(format!("dropped at {drone}"));
+        }
+        if let Some((drone, nack_type)) = self.nacked_at {
+            parts.push(format!("nacked at {drone} ({nack_type:?})"));
+        }
+        if !self.delivered && self.dropped_at.is_none() && self.nacked_at.is_none() {
+            parts.push("lost".to_string());
+        }
+        for drone in &self.shortcuts {
+            parts.push(format!("shortcut at {drone}"));
+        }
+
+        write!(
+            f,
+            "session {} fragment {}: {}",
+            self.session_id,
+            self.fragment_index,
+            parts.join(", ")
+        )
+    }
+}
+
+/// Journeys of every fragment of an event log, by session and fragment index.
+/// Acks and nacks are matched to their fragment.
+///
+/// The times are the ones the events were received by the controller,
+/// so the time of each hop is approximated.
+#[derive(Debug, Clone, Default)]
+pub struct Journeys {
+    journeys: BTreeMap<(u64, u64), Journey>,
+}
+
+impl Journeys {
+    #[must_use]
+    pub fn from_records(records: &[EventRecord]) -> Self {
+        let mut records = records.iter().collect::<Vec<_>>();
+        records.sort_by_key(|record| record.at_ns);
+
+        let mut journeys = BTreeMap::new();
+        for record in records {
+            let packet = &record.packet;
+            let Some(fragment_index) = packet.fragment_index else {
+                continue;
+            };
+            let journey = journeys
+                .entry((packet.session_id, fragment_index))
+                .or_insert_with(|| Journey::new(packet.session_id, fragment_index));
+
+            match packet.kind {
+                PacketKind::MsgFragment => journey.add_fragment_event(record),
+                PacketKind::Ack | PacketKind::Nack => journey.add_response_event(record),
+                PacketKind::FloodRequest | PacketKind::FloodResponse => {}
+            }
+        }
+
+        // Acks or shortcuts alone do not say anything about the fragment
+        journeys.retain(|_, journey: &mut Journey| !journey.route.is_empty());
+        for journey in journeys.values_mut() {
+            journey.sort_hops();
+        }
+        Self { journeys }
+    }
+
+    #[must_use]
+    pub fn get(&self, session_id: u64, fragment_index: u64) -> Option<&Journey> {
+        self.journeys.get(&(session_id, fragment_index))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Journey> {
+        self.journeys.values()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.journeys.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.journeys.is_empty()
+    }
+}
+
+impl Display for Journeys {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, journey) in self.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{journey}")?;
+        }
+        Ok(())
+    }
+}
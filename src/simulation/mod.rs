@@ -0,0 +1,30 @@
+mod config;
+mod controller;
+mod dot;
+mod generate;
+mod journey;
+mod network;
+mod record;
+mod scenario;
+mod script;
+mod test;
+mod topology;
+mod trace;
+
+pub use config::{load_config, parse_config, validate_config, NetworkConfigError};
+pub use controller::{describe_packet, Controller, LoggedEvent, Summary};
+pub use dot::{DotGraph, Overlay};
+pub use generate::{GenerateError, NetworkGenerator, Shape};
+pub use journey::{Hop, Journey, Journeys};
+pub use network::{DroneHandle, Endpoint, Network, SimulationError};
+pub use record::{
+    load_records, read_records, EventKind, EventRecord, PacketSummary, RecordError, RecordedNack,
+    Recorder,
+};
+pub use scenario::{
+    Command, Expectation, ExpectationResult, Flow, Scenario, ScenarioError, ScenarioReport,
+    TimedCommand,
+};
+pub use script::{Action, Script, ScriptError, Step};
+pub use topology::{NodeKind, Topology};
+pub use trace::ChromeTrace;
@@ -0,0 +1,420 @@
+use crate::drone::{ConfigError, DroneBuilder, DroneStats, DroneStatsHandle, RustyDrone};
+use crate::simulation::{NodeKind, Topology};
+use crossbeam_channel::{unbounded, Receiver, Select, Sender};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use std::{iter, thread};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NodeType, Packet};
+
+/// Reason why an operation on a [`Network`] failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationError {
+    /// No node with this id exists.
+    UnknownNode(NodeId),
+    /// The operation requires a drone, but the node is a client or server.
+    NotADrone(NodeId),
+    /// The operation requires a client or server, but the node is a drone.
+    NotAnEndpoint(NodeId),
+    /// The two nodes are not linked.
+    NotConnected(NodeId, NodeId),
+    /// The packet has no valid next hop.
+    NoNextHop,
+    /// The drone already stopped running.
+    Stopped(NodeId),
+    /// A drone could not be created.
+    Config(NodeId, ConfigError),
+    /// A scripted step is too far from the start of the simulation to be scheduled.
+    TooLate(Duration),
+}
+
+impl Display for SimulationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimulationError::UnknownNode(id) => write!(f, "node {id} does not exist"),
+            SimulationError::NotADrone(id) => write!(f, "node {id} is not a drone"),
+            SimulationError::NotAnEndpoint(id) => write!(f, "node {id} is not a client or server"),
+            SimulationError::NotConnected(a, b) => write!(f, "nodes {a} and {b} are not linked"),
+            SimulationError::NoNextHop => write!(f, "packet has no valid next hop"),
+            SimulationError::Stopped(id) => write!(f, "drone {id} is not running"),
+            SimulationError::Config(id, err) => write!(f, "drone {id}: {err}"),
+            SimulationError::TooLate(at) => write!(f, "step at {at:?} is too late to schedule"),
+        }
+    }
+}
+
+impl Error for SimulationError {}
+
+/// A [`RustyDrone`] running on its own thread.
+pub struct DroneHandle {
+    command_send: Sender<DroneCommand>,
+    event_recv: Receiver<DroneEvent>,
+    stats: Arc<DroneStatsHandle>,
+    thread: Option<JoinHandle<()>>,
+    /// The drone was told to crash, it only drains its queued packets.
+    crashed: bool,
+}
+
+/// Stand-in for a client or server: it only collects the packets sent to it.
+pub struct Endpoint {
+    id: NodeId,
+    node_type: NodeType,
+    packet_recv: Receiver<Packet>,
+}
+
+impl Endpoint {
+    #[must_use]
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    #[must_use]
+    pub fn node_type(&self) -> NodeType {
+        self.node_type.clone()
+    }
+
+    /// Channel of the packets received by this endpoint.
+    #[must_use]
+    pub fn packet_recv(&self) -> &Receiver<Packet> {
+        &self.packet_recv
+    }
+}
+
+/// A set of drones, clients and servers connected as described by a [`Topology`].
+/// Every drone runs on its own thread, the network acts as Simulation Controller.
+pub struct Network {
+    topology: Topology,
+    drones: BTreeMap<NodeId, DroneHandle>,
+    endpoints: BTreeMap<NodeId, Endpoint>,
+    /// Channel to send packets to each node.
+    packet_send: HashMap<NodeId, Sender<Packet>>,
+}
+
+impl Network {
+    /// Create and start every node of the topology, drones use their default options.
+    /// # Errors
+    /// If a drone cannot be created.
+    pub fn spawn(topology: &Topology) -> Result<Self, SimulationError> {
+        Self::spawn_with(topology, |_, builder| builder)
+    }
+
+    /// Create and start every node of the topology.
+    /// `configure` can change the options of each drone before it is built.
+    /// # Errors
+    /// If a drone cannot be created.
+    pub fn spawn_with(
+        topology: &Topology,
+        mut configure: impl FnMut(NodeId, DroneBuilder) -> DroneBuilder,
+    ) -> Result<Self, SimulationError> {
+        let mut packet_send = HashMap::new();
+        let mut packet_recv = HashMap::new();
+        for (id, _) in topology.nodes() {
+            let (sender, receiver) = unbounded();
+            packet_send.insert(id, sender);
+            packet_recv.insert(id, receiver);
+        }
+
+        let mut drones = BTreeMap::new();
+        let mut endpoints = BTreeMap::new();
+        for (id, kind) in topology.nodes() {
+            let receiver = packet_recv.remove(&id).unwrap_or_else(|| unbounded().1);
+
+            let NodeKind::Drone { pdr } = kind else {
+                endpoints.insert(
+                    id,
+                    Endpoint {
+                        id,
+                        node_type: kind.node_type(),
+                        packet_recv: receiver,
+                    },
+                );
+                continue;
+            };
+
+            let (controller_send, event_recv) = unbounded();
+            let (command_send, controller_recv) = unbounded();
+            let neighbors = topology
+                .neighbors(id)
+                .filter_map(|n| Some((n, packet_send.get(&n)?.clone())))
+                .collect();
+
+            let builder = RustyDrone::builder()
+                .id(id)
+                .controller_send(controller_send)
+                .controller_recv(controller_recv)
+                .packet_recv(receiver)
+                .packet_send(neighbors)
+                .pdr(pdr);
+            let mut drone = configure(id, builder)
+                .build()
+                .map_err(|err| SimulationError::Config(id, err))?;
+
+            let stats = drone.stats();
+            let thread = Some(thread::spawn(move || drone.run()));
+
+            drones.insert(
+                id,
+                DroneHandle {
+                    command_send,
+                    event_recv,
+                    stats,
+                    thread,
+                    crashed: false,
+                },
+            );
+        }
+
+        Ok(Self {
+            topology: topology.clone(),
+            drones,
+            endpoints,
+            packet_send,
+        })
+    }
+
+    /// Current topology, updated by the operations on the network.
+    #[must_use]
+    pub fn topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    /// Ids of all the drones.
+    pub fn drone_ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.drones.keys().copied()
+    }
+
+    /// If the drone was crashed through [`Network::crash`].
+    #[must_use]
+    pub fn is_crashed(&self, id: NodeId) -> bool {
+        self.drones.get(&id).is_some_and(|drone| drone.crashed)
+    }
+
+    #[must_use]
+    pub fn endpoint(&self, id: NodeId) -> Option<&Endpoint> {
+        self.endpoints.get(&id)
+    }
+
+    /// All the clients and servers.
+    pub fn endpoints(&self) -> impl Iterator<Item = &Endpoint> {
+        self.endpoints.values()
+    }
+
+    /// Send a command to a drone.
+    /// # Errors
+    /// If the node is not a running drone.
+    pub fn command(&self, id: NodeId, command: DroneCommand) -> Result<(), SimulationError> {
+        let drone = self.drone(id)?;
+        if drone.crashed {
+            return Err(SimulationError::Stopped(id));
+        }
+        drone
+            .command_send
+            .send(command)
+            .map_err(|_| SimulationError::Stopped(id))
+    }
+
+    /// # Errors
+    /// If the node is not a running drone.
+    pub fn set_pdr(&self, id: NodeId, pdr: f32) -> Result<(), SimulationError> {
+        self.command(id, DroneCommand::SetPacketDropRate(pdr))
+    }
+
+    /// Link two nodes, notifying the drones among them.
+    /// # Errors
+    /// If one of the nodes does not exist.
+    pub fn add_link(&mut self, a: NodeId, b: NodeId) -> Result<(), SimulationError> {
+        let sender_a = self.node_sender(a)?;
+        let sender_b = self.node_sender(b)?;
+
+        if self.drones.contains_key(&a) {
+            self.command(a, DroneCommand::AddSender(b, sender_b))?;
+        }
+        if self.drones.contains_key(&b) {
+            self.command(b, DroneCommand::AddSender(a, sender_a))?;
+        }
+        self.topology.connect(a, b);
+        Ok(())
+    }
+
+    /// Remove the link between two nodes, notifying the drones among them.
+    /// # Errors
+    /// If the nodes are not linked.
+    pub fn remove_link(&mut self, a: NodeId, b: NodeId) -> Result<(), SimulationError> {
+        if !self.topology.are_connected(a, b) {
+            return Err(SimulationError::NotConnected(a, b));
+        }
+
+        if self.drones.contains_key(&a) {
+            self.command(a, DroneCommand::RemoveSender(b))?;
+        }
+        if self.drones.contains_key(&b) {
+            self.command(b, DroneCommand::RemoveSender(a))?;
+        }
+        self.topology.disconnect(a, b);
+        Ok(())
+    }
+
+    /// Crash a drone as the protocol requires: its neighbors remove it, then it is told to crash.
+    /// # Errors
+    /// If the node is not a running drone.
+    pub fn crash(&mut self, id: NodeId) -> Result<(), SimulationError> {
+        if self.drone(id)?.crashed {
+            return Err(SimulationError::Stopped(id));
+        }
+
+        let neighbors = self.topology.neighbors(id).collect::<Vec<_>>();
+        for neighbor in neighbors {
+            if self.drones.contains_key(&neighbor) {
+                // The neighbor may have crashed already
+                let _ = self.command(neighbor, DroneCommand::RemoveSender(id));
+            }
+            self.topology.disconnect(id, neighbor);
+        }
+
+        // Nobody can send packets to the crashed drone anymore
+        self.packet_send.remove(&id);
+        self.command(id, DroneCommand::Crash)?;
+        if let Some(drone) = self.drones.get_mut(&id) {
+            drone.crashed = true;
+        }
+        Ok(())
+    }
+
+    /// Send a packet from a client or server to the next hop of its routing header.
+    /// The routing header must already point to the next hop (as if it was forwarded).
+    /// # Errors
+    /// If the node is not an endpoint or it is not linked to the next hop.
+    pub fn send_from(&self, from: NodeId, packet: Packet) -> Result<(), SimulationError> {
+        if !self.endpoints.contains_key(&from) {
+            return Err(SimulationError::NotAnEndpoint(from));
+        }
+
+        let next = packet
+            .routing_header
+            .current_hop()
+            .ok_or(SimulationError::NoNextHop)?;
+        if !self.topology.are_connected(from, next) {
+            return Err(SimulationError::NotConnected(from, next));
+        }
+        self.inject(next, packet)
+    }
+
+    /// Put a packet directly in the receiving channel of a node.
+    /// # Errors
+    /// If the node does not exist or crashed.
+    pub fn inject(&self, to: NodeId, packet: Packet) -> Result<(), SimulationError> {
+        self.node_sender(to)?
+            .send(packet)
+            .map_err(|_| SimulationError::Stopped(to))
+    }
+
+    /// Deliver a packet sent through the Simulation Controller shortcut to its destination.
+    /// # Errors
+    /// If the destination does not exist or crashed.
+    pub fn deliver_shortcut(&self, packet: Packet) -> Result<(), SimulationError> {
+        let destination = *packet
+            .routing_header
+            .hops
+            .last()
+            .ok_or(SimulationError::NoNextHop)?;
+        self.inject(destination, packet)
+    }
+
+    /// Wait for the next event sent by any drone.
+    /// # Returns
+    /// The drone that sent the event and the event, `None` on timeout.
+    #[must_use]
+    pub fn next_event(&self, timeout: Duration) -> Option<(NodeId, DroneEvent)> {
+        let deadline = Instant::now() + timeout;
+        let receivers = self
+            .drones
+            .iter()
+            .map(|(id, drone)| (*id, &drone.event_recv))
+            .collect::<Vec<_>>();
+
+        let mut select = Select::new();
+        for (_, receiver) in &receivers {
+            select.recv(receiver);
+        }
+
+        loop {
+            let operation = select.select_deadline(deadline).ok()?;
+            let index = operation.index();
+            let (id, receiver) = receivers[index];
+            match operation.recv(receiver) {
+                Ok(event) => return Some((id, event)),
+                // The drone stopped and nobody can send its events anymore
+                Err(_) => select.remove(index),
+            }
+        }
+    }
+
+    /// All the events already sent by the drones, without waiting.
+    #[must_use]
+    pub fn drain_events(&self) -> Vec<(NodeId, DroneEvent)> {
+        self.drones
+            .iter()
+            .flat_map(|(id, drone)| iter::repeat(*id).zip(drone.event_recv.try_iter()))
+            .collect()
+    }
+
+    /// Current counters of a drone.
+    /// # Errors
+    /// If the node is not a drone.
+    pub fn stats(&self, id: NodeId) -> Result<DroneStats, SimulationError> {
+        Ok(self.drone(id)?.stats.snapshot())
+    }
+
+    /// Crash every drone and wait for all of them to stop.
+    pub fn join(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // Drop our channels to the nodes, then let every drone drop its own
+        self.packet_send.clear();
+        let all_ids = self.topology.nodes().map(|(id, _)| id).collect::<Vec<_>>();
+        for drone in self.drones.values() {
+            for id in &all_ids {
+                let _ = drone.command_send.send(DroneCommand::RemoveSender(*id));
+            }
+            let _ = drone.command_send.send(DroneCommand::Crash);
+        }
+
+        for drone in self.drones.values_mut() {
+            if let Some(thread) = drone.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    fn drone(&self, id: NodeId) -> Result<&DroneHandle, SimulationError> {
+        self.drones
+            .get(&id)
+            .ok_or(if self.endpoints.contains_key(&id) {
+                SimulationError::NotADrone(id)
+            } else {
+                SimulationError::UnknownNode(id)
+            })
+    }
+
+    fn node_sender(&self, id: NodeId) -> Result<Sender<Packet>, SimulationError> {
+        match self.packet_send.get(&id) {
+            Some(sender) => Ok(sender.clone()),
+            None if self.topology.kind(id).is_some() => Err(SimulationError::Stopped(id)),
+            None => Err(SimulationError::UnknownNode(id)),
+        }
+    }
+}
+
+impl Drop for Network {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
@@ -0,0 +1,241 @@
+use crate::drone::PacketKind;
+use crate::simulation::LoggedEvent;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+/// Kind of a `DroneEvent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    PacketSent,
+    PacketDropped,
+    ControllerShortcut,
+}
+
+/// Serializable copy of a `NackType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedNack {
+    ErrorInRouting(NodeId),
+    DestinationIsDrone,
+    Dropped,
+    UnexpectedRecipient(NodeId),
+}
+
+impl From<&NackType> for RecordedNack {
+    fn from(nack_type: &NackType) -> Self {
+        match *nack_type {
+            NackType::ErrorInRouting(id) => RecordedNack::ErrorInRouting(id),
+            NackType::DestinationIsDrone => RecordedNack::DestinationIsDrone,
+            NackType::Dropped => RecordedNack::Dropped,
+            NackType::UnexpectedRecipient(id) => RecordedNack::UnexpectedRecipient(id),
+        }
+    }
+}
+
+/// Everything of a packet except its data and flood path trace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PacketSummary {
+    pub kind: PacketKind,
+    pub session_id: u64,
+    /// Index of the fragment, for fragments, acks and nacks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fragment_index: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_n_fragments: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nack_type: Option<RecordedNack>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flood_id: Option<u64>,
+    pub hops: Vec<NodeId>,
+    pub hop_index: usize,
+}
+
+impl From<&Packet> for PacketSummary {
+    fn from(packet: &Packet) -> Self {
+        let mut summary = PacketSummary {
+            kind: PacketKind::MsgFragment,
+            session_id: packet.session_id,
+            fragment_index: None,
+            total_n_fragments: None,
+            nack_type: None,
+            flood_id: None,
+            hops: packet.routing_header.hops.clone(),
+            hop_index: packet.routing_header.hop_index,
+        };
+
+        match &packet.pack_type {
+            PacketType::MsgFragment(fragment) => {
+                summary.fragment_index = Some(fragment.fragment_index);
+                summary.total_n_fragments = Some(fragment.total_n_fragments);
+            }
+            PacketType::Ack(ack) => {
+                summary.kind = PacketKind::Ack;
+                summary.fragment_index = Some(ack.fragment_index);
+            }
+            PacketType::Nack(nack) => {
+                summary.kind = PacketKind::Nack;
+                summary.fragment_index = Some(nack.fragment_index);
+                summary.nack_type = Some(RecordedNack::from(&nack.nack_type));
+            }
+            PacketType::FloodRequest(flood) => {
+                summary.kind = PacketKind::FloodRequest;
+                summary.flood_id = Some(flood.flood_id);
+            }
+            PacketType::FloodResponse(flood) => {
+                summary.kind = PacketKind::FloodResponse;
+                summary.flood_id = Some(flood.flood_id);
+            }
+        }
+        summary
+    }
+}
+
+/// One line of a JSON Lines event log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    /// Nanoseconds since the start of the simulation.
+    pub at_ns: u64,
+    /// Drone that sent the event.
+    pub drone: NodeId,
+    pub event: EventKind,
+    pub packet: PacketSummary,
+}
+
+impl EventRecord {
+    #[must_use]
+    pub fn new(at: Duration, drone: NodeId, event: &DroneEvent) -> Self {
+        let (kind, packet) = match event {
+            DroneEvent::PacketSent(packet) => (EventKind::PacketSent, packet),
+            DroneEvent::PacketDropped(packet) => (EventKind::PacketDropped, packet),
+            DroneEvent::ControllerShortcut(packet) => (EventKind::ControllerShortcut, packet),
+        };
+        Self {
+            at_ns: u64::try_from(at.as_nanos()).unwrap_or(u64::MAX),
+            drone,
+            event: kind,
+            packet: PacketSummary::from(packet),
+        }
+    }
+
+    /// Time since the start of the simulation.
+    #[must_use]
+    pub fn at(&self) -> Duration {
+        Duration::from_nanos(self.at_ns)
+    }
+}
+
+impl From<&LoggedEvent> for EventRecord {
+    fn from(logged: &LoggedEvent) -> Self {
+        Self::new(logged.at, logged.drone, &logged.event)
+    }
+}
+
+/// Write events as JSON Lines, one [`EventRecord`] per line.
+/// After the first error nothing else is written, the error is returned by [`Recorder::finish`].
+pub struct Recorder {
+    writer: Box<dyn Write>,
+    error: Option<io::Error>,
+}
+
+impl Recorder {
+    #[must_use]
+    pub fn new(writer: impl Write + 'static) -> Self {
+        Self {
+            writer: Box::new(writer),
+            error: None,
+        }
+    }
+
+    /// Recorder writing to a new file, or truncating an existing one.
+    /// # Errors
+    /// If the file cannot be created.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    pub fn record(&mut self, event: &LoggedEvent) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let record = EventRecord::from(event);
+        let result = serde_json::to_writer(&mut self.writer, &record)
+            .map_err(io::Error::from)
+            .and_then(|()| self.writer.write_all(b"\n"));
+        if let Err(err) = result {
+            self.error = Some(err);
+        }
+    }
+
+    /// Flush the records.
+    /// # Errors
+    /// The first error met while writing or flushing.
+    pub fn finish(mut self) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.writer.flush()
+    }
+}
+
+/// Reason why an event log cannot be read.
+#[derive(Debug)]
+pub enum RecordError {
+    Io(io::Error),
+    /// The line, starting from 1, is not a valid record.
+    Parse {
+        line: usize,
+        error: serde_json::Error,
+    },
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordError::Io(err) => write!(f, "cannot read the event log: {err}"),
+            RecordError::Parse { line, error } => write!(f, "line {line}: {error}"),
+        }
+    }
+}
+
+impl Error for RecordError {}
+
+impl From<io::Error> for RecordError {
+    fn from(err: io::Error) -> Self {
+        RecordError::Io(err)
+    }
+}
+
+/// Read the records of a JSON Lines event log, skipping empty lines.
+/// # Errors
+/// If the log cannot be read or a line is not a valid record.
+pub fn read_records(reader: impl BufRead) -> Result<Vec<EventRecord>, RecordError> {
+    let mut records = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .map_err(|error| RecordError::Parse { line: i + 1, error })?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Read the records of a JSON Lines event log file.
+/// # Errors
+/// If the file cannot be read or a line is not a valid record.
+pub fn load_records(path: impl AsRef<Path>) -> Result<Vec<EventRecord>, RecordError> {
+    read_records(BufReader::new(File::open(path)?))
+}
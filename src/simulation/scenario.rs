@@ -0,0 +1,515 @@
+use crate::drone::DroneBuilder;
+use crate::simulation::{
+    load_config, validate_config, Action, Controller, Network, NetworkConfigError, Script,
+    SimulationError, Step, Summary, Topology,
+};
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fs, io};
+use wg_2024::config::{Client, Config, Drone, Server};
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::PacketType;
+
+/// Declarative description of a simulation: topology, traffic, timed commands
+/// and the outcomes expected from the drones.
+///
+/// ```toml
+/// seed = 42
+/// settle_ms = 200
+///
+/// # Either a network initialization file (relative to the scenario file) ...
+/// network = "network.toml"
+/// # ... or the same `[[drone]]`, `[[client]]` and `[[server]]` tables inline
+///
+/// [[flow]]
+/// from = 1
+/// to = 21
+/// fragments = 5
+/// messages = 3        # send 3 messages ...
+/// interval_ms = 50    # ... one every 50 ms
+///
+/// [[command]]
+/// at_ms = 100
+/// action = "crash"    # or "pdr" (with `pdr`), "add-link" and "remove-link" (with `a` and `b`)
+/// drone = 12
+///
+/// [[expect]]
+/// type = "delivered"  # at least `at_least` fragments (all those sent if omitted) from 1 reach 21
+/// from = 1
+/// to = 21
+///
+/// [[expect]]
+/// type = "dropped"    # also "nacked" and "shortcut", at least `at_least` (default 1) times
+/// drone = 11
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Simulation seed of the drones, non reproducible if missing.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// After the last command, wait until nothing happens for this long.
+    #[serde(default = "default_settle_ms")]
+    pub settle_ms: u64,
+    /// Network initialization file, relative to the scenario file.
+    #[serde(default)]
+    pub network: Option<PathBuf>,
+    #[serde(default)]
+    pub drone: Vec<Drone>,
+    #[serde(default)]
+    pub client: Vec<Client>,
+    #[serde(default)]
+    pub server: Vec<Server>,
+    #[serde(default)]
+    pub flow: Vec<Flow>,
+    #[serde(default)]
+    pub command: Vec<TimedCommand>,
+    #[serde(default)]
+    pub expect: Vec<Expectation>,
+}
+
+fn default_settle_ms() -> u64 {
+    200
+}
+
+fn default_one() -> u64 {
+    1
+}
+
+/// Messages sent from a client or server to another.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Flow {
+    #[serde(default)]
+    pub at_ms: u64,
+    pub from: NodeId,
+    pub to: NodeId,
+    /// Fragments of each message.
+    #[serde(default = "default_one")]
+    pub fragments: u64,
+    #[serde(default = "default_one")]
+    pub messages: u64,
+    /// Time between the start of two messages.
+    #[serde(default)]
+    pub interval_ms: u64,
+}
+
+impl Flow {
+    /// Start time of the last message, `None` if it does not fit in a `u64`.
+    fn last_ms(&self) -> Option<u64> {
+        self.messages
+            .saturating_sub(1)
+            .checked_mul(self.interval_ms)?
+            .checked_add(self.at_ms)
+    }
+}
+
+/// Command of the Simulation Controller and when to send it.
+/// Unknown keys are rejected by [`Command`], which gets every key but `at_ms`
+/// (serde cannot deny them on a struct with a flattened field).
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimedCommand {
+    #[serde(default)]
+    pub at_ms: u64,
+    #[serde(flatten)]
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum Command {
+    Crash { drone: NodeId },
+    Pdr { drone: NodeId, pdr: f32 },
+    AddLink { a: NodeId, b: NodeId },
+    RemoveLink { a: NodeId, b: NodeId },
+}
+
+/// Outcome the scenario must produce.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum Expectation {
+    /// At least `at_least` fragments sent by `from` reach `to`, all the ones sent if `None`.
+    Delivered {
+        from: NodeId,
+        to: NodeId,
+        #[serde(default)]
+        at_least: Option<u64>,
+    },
+    /// No fragment sent by `from` reaches `to`.
+    NotDelivered { from: NodeId, to: NodeId },
+    /// The drone emits at least `at_least` `PacketDropped` events.
+    Dropped {
+        drone: NodeId,
+        #[serde(default = "default_one")]
+        at_least: u64,
+    },
+    /// The drone creates at least `at_least` nacks.
+    Nacked {
+        drone: NodeId,
+        #[serde(default = "default_one")]
+        at_least: u64,
+    },
+    /// The drone emits at least `at_least` `ControllerShortcut` events.
+    Shortcut {
+        drone: NodeId,
+        #[serde(default = "default_one")]
+        at_least: u64,
+    },
+    /// At least this fraction of all the fragments sent is delivered.
+    DeliveryRatio { at_least: f64 },
+}
+
+impl Display for Expectation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expectation::Delivered {
+                from,
+                to,
+                at_least: Some(n),
+            } => write!(f, "at least {n} fragments from {from} reach {to}"),
+            Expectation::Delivered { from, to, .. } => {
+                write!(f, "every fragment from {from} reaches {to}")
+            }
+            Expectation::NotDelivered { from, to } => {
+                write!(f, "no fragment from {from} reaches {to}")
+            }
+            Expectation::Dropped { drone, at_least } => {
+                write!(f, "drone {drone} drops at least {at_least} packets")
+            }
+            Expectation::Nacked { drone, at_least } => {
+                write!(f, "drone {drone} creates at least {at_least} nacks")
+            }
+            Expectation::Shortcut { drone, at_least } => {
+                write!(f, "drone {drone} uses at least {at_least} shortcuts")
+            }
+            Expectation::DeliveryRatio { at_least } => {
+                write!(f, "delivery ratio is at least {:.1}%", at_least * 100.0)
+            }
+        }
+    }
+}
+
+/// Whether an expectation was met, and what was observed.
+#[derive(Debug, Clone)]
+pub struct ExpectationResult {
+    pub expectation: Expectation,
+    pub passed: bool,
+    pub observed: String,
+}
+
+/// Outcome of running a [`Scenario`].
+#[derive(Debug, Clone)]
+pub struct ScenarioReport {
+    pub summary: Summary,
+    pub results: Vec<ExpectationResult>,
+}
+
+impl ScenarioReport {
+    /// If every expectation was met.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| r.passed)
+    }
+}
+
+impl Display for ScenarioReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.summary)?;
+        for result in &self.results {
+            let status = if result.passed { "PASS" } else { "FAIL" };
+            writeln!(
+                f,
+                "{status}  {} (observed {})",
+                result.expectation, result.observed
+            )?;
+        }
+
+        let passed = self.results.iter().filter(|r| r.passed).count();
+        write!(f, "{passed}/{} expectations passed", self.results.len())
+    }
+}
+
+/// Reason why a scenario cannot be loaded or run.
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    /// Both a network file and inline nodes were given.
+    TwoTopologies,
+    /// The last message of the flow between the two nodes is sent too late to be represented.
+    FlowTooLong {
+        from: NodeId,
+        to: NodeId,
+    },
+    Network(NetworkConfigError),
+    Simulation(SimulationError),
+}
+
+impl Display for ScenarioError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScenarioError::Io(err) => write!(f, "cannot read scenario: {err}"),
+            ScenarioError::Parse(err) => write!(f, "invalid scenario: {err}"),
+            ScenarioError::TwoTopologies => {
+                write!(f, "scenario has both a network file and inline nodes")
+            }
+            ScenarioError::FlowTooLong { from, to } => {
+                write!(f, "flow from {from} to {to} ends too late")
+            }
+            ScenarioError::Network(err) => write!(f, "{err}"),
+            ScenarioError::Simulation(err) => write!(f, "scenario failed: {err}"),
+        }
+    }
+}
+
+impl Error for ScenarioError {}
+
+impl From<io::Error> for ScenarioError {
+    fn from(err: io::Error) -> Self {
+        ScenarioError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ScenarioError {
+    fn from(err: toml::de::Error) -> Self {
+        ScenarioError::Parse(err)
+    }
+}
+
+impl From<NetworkConfigError> for ScenarioError {
+    fn from(err: NetworkConfigError) -> Self {
+        ScenarioError::Network(err)
+    }
+}
+
+impl From<SimulationError> for ScenarioError {
+    fn from(err: SimulationError) -> Self {
+        ScenarioError::Simulation(err)
+    }
+}
+
+impl Scenario {
+    /// Load a scenario file, together with its network file if any.
+    /// # Errors
+    /// If a file cannot be read or parsed, or the network is not valid.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or(Path::new(""));
+        Self::load(&fs::read_to_string(path)?, base)
+    }
+
+    /// Parse a scenario, a network file is relative to the current directory.
+    /// # Errors
+    /// If the scenario cannot be parsed, or the network is not valid.
+    pub fn parse(text: &str) -> Result<Self, ScenarioError> {
+        Self::load(text, Path::new(""))
+    }
+
+    fn load(text: &str, base: &Path) -> Result<Self, ScenarioError> {
+        let mut scenario: Self = toml::from_str(text)?;
+        if let Some(network) = scenario.network.take() {
+            if !scenario.drone.is_empty()
+                || !scenario.client.is_empty()
+                || !scenario.server.is_empty()
+            {
+                return Err(ScenarioError::TwoTopologies);
+            }
+            scenario.set_network(load_config(base.join(network))?);
+        }
+
+        validate_config(&scenario.network_config())?;
+        scenario.check_flows()?;
+        Ok(scenario)
+    }
+
+    /// Replace the nodes of the scenario.
+    pub fn set_network(&mut self, config: Config) {
+        self.drone = config.drone;
+        self.client = config.client;
+        self.server = config.server;
+    }
+
+    /// Nodes of the scenario, as in a network initialization file.
+    #[must_use]
+    pub fn network_config(&self) -> Config {
+        Config {
+            drone: self.drone.clone(),
+            client: self.client.clone(),
+            server: self.server.clone(),
+        }
+    }
+
+    /// Check that the time of every message fits.
+    fn check_flows(&self) -> Result<(), ScenarioError> {
+        for flow in &self.flow {
+            if flow.last_ms().is_none() {
+                return Err(ScenarioError::FlowTooLong {
+                    from: flow.from,
+                    to: flow.to,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Flows and commands as a timed script.
+    /// # Errors
+    /// If a flow ends too late, see [`ScenarioError::FlowTooLong`].
+    pub fn script(&self) -> Result<Script, ScenarioError> {
+        self.check_flows()?;
+        let flows = self.flow.iter().flat_map(|flow| {
+            (0..flow.messages).map(|i| Step {
+                at: Duration::from_millis(flow.at_ms + i * flow.interval_ms),
+                action: Action::Send {
+                    from: flow.from,
+                    to: flow.to,
+                    fragments: flow.fragments,
+                },
+            })
+        });
+
+        let commands = self.command.iter().map(|timed| Step {
+            at: Duration::from_millis(timed.at_ms),
+            action: match timed.command {
+                Command::Crash { drone } => Action::Crash(drone),
+                Command::Pdr { drone, pdr } => Action::SetPdr(drone, pdr),
+                Command::AddLink { a, b } => Action::AddLink(a, b),
+                Command::RemoveLink { a, b } => Action::RemoveLink(a, b),
+            },
+        });
+
+        Ok(Script::new(flows.chain(commands).collect()))
+    }
+
+    /// Start the network, run the script and check the expectations.
+    /// # Errors
+    /// If the network cannot be started or a command cannot be applied.
+    pub fn run(&self) -> Result<ScenarioReport, ScenarioError> {
+        let mut controller = self.start()?;
+        let result = self.run_script(&mut controller);
+        let report = self.evaluate(&controller);
+
+        controller.into_network().join();
+        result?;
+        Ok(report)
+    }
+
+    /// Start the network of the scenario, without running the script.
+    /// # Errors
+    /// If a drone cannot be created.
+    pub fn start(&self) -> Result<Controller, ScenarioError> {
+        self.start_with(|_, builder| builder)
+    }
+
+    /// Start the network of the scenario, `configure` can change the options of each drone.
+    /// # Errors
+    /// If a drone cannot be created.
+    pub fn start_with(
+        &self,
+        mut configure: impl FnMut(NodeId, DroneBuilder) -> DroneBuilder,
+    ) -> Result<Controller, ScenarioError> {
+        let topology = Topology::from_config(&self.network_config());
+        let network = Network::spawn_with(&topology, |id, builder| {
+            let builder = configure(id, builder);
+            match self.seed {
+                Some(seed) => builder.simulation_seed(seed),
+                None => builder,
+            }
+        })?;
+        Ok(Controller::new(network))
+    }
+
+    /// Run flows and commands, then wait for the network to settle.
+    /// # Errors
+    /// If a command cannot be applied, the remaining ones are skipped.
+    pub fn run_script(&self, controller: &mut Controller) -> Result<(), ScenarioError> {
+        let settle = Duration::from_millis(self.settle_ms);
+        Ok(controller.run_script(&self.script()?, settle)?)
+    }
+
+    /// Check the expectations against what happened so far.
+    #[must_use]
+    pub fn evaluate(&self, controller: &Controller) -> ScenarioReport {
+        ScenarioReport {
+            summary: controller.summary(),
+            results: self
+                .expect
+                .iter()
+                .map(|expectation| self.check(expectation, controller))
+                .collect(),
+        }
+    }
+
+    fn check(&self, expectation: &Expectation, controller: &Controller) -> ExpectationResult {
+        let delivered = |from: NodeId, to: NodeId| {
+            controller
+                .received()
+                .iter()
+                .filter(|(id, packet)| {
+                    *id == to
+                        && matches!(packet.pack_type, PacketType::MsgFragment(_))
+                        && packet.routing_header.hops.first() == Some(&from)
+                        && packet.routing_header.hops.last() == Some(&to)
+                })
+                .count() as u64
+        };
+        let events = |drone: NodeId, matches: fn(&DroneEvent) -> bool| {
+            controller
+                .events()
+                .iter()
+                .filter(|e| e.drone == drone && matches(&e.event))
+                .count() as u64
+        };
+
+        let (passed, observed) = match *expectation {
+            Expectation::Delivered { from, to, at_least } => {
+                let count = delivered(from, to);
+                let required = at_least.unwrap_or_else(|| self.fragments_sent(from, to));
+                (count >= required, format!("{count} delivered"))
+            }
+            Expectation::NotDelivered { from, to } => {
+                let count = delivered(from, to);
+                (count == 0, format!("{count} delivered"))
+            }
+            Expectation::Dropped { drone, at_least } => {
+                let count = events(drone, |e| matches!(e, DroneEvent::PacketDropped(_)));
+                (count >= at_least, format!("{count} dropped"))
+            }
+            Expectation::Nacked { drone, at_least } => {
+                let count = controller
+                    .network()
+                    .stats(drone)
+                    .map(|stats| stats.nacks.values().sum::<u64>())
+                    .unwrap_or_default();
+                (count >= at_least, format!("{count} nacks"))
+            }
+            Expectation::Shortcut { drone, at_least } => {
+                let count = events(drone, |e| matches!(e, DroneEvent::ControllerShortcut(_)));
+                (count >= at_least, format!("{count} shortcuts"))
+            }
+            Expectation::DeliveryRatio { at_least } => {
+                let ratio = controller.summary().delivery_ratio();
+                (ratio >= at_least, format!("{:.1}%", ratio * 100.0))
+            }
+        };
+
+        ExpectationResult {
+            expectation: expectation.clone(),
+            passed,
+            observed,
+        }
+    }
+
+    /// Fragments the flows send from `from` to `to`.
+    fn fragments_sent(&self, from: NodeId, to: NodeId) -> u64 {
+        self.flow
+            .iter()
+            .filter(|flow| flow.from == from && flow.to == to)
+            .map(|flow| flow.fragments * flow.messages)
+            .sum()
+    }
+}
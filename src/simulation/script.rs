@@ -0,0 +1,173 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+use std::{fs, io};
+use wg_2024::network::NodeId;
+
+/// Something the Simulation Controller does to the network.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Crash(NodeId),
+    SetPdr(NodeId, f32),
+    AddLink(NodeId, NodeId),
+    RemoveLink(NodeId, NodeId),
+    /// Send a message of `fragments` fragments from a client or server to another,
+    /// along the shortest path at the moment of sending.
+    Send {
+        from: NodeId,
+        to: NodeId,
+        fragments: u64,
+    },
+}
+
+/// An action and when to do it, relative to the start of the simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub at: Duration,
+    pub action: Action,
+}
+
+/// Timed list of actions, ordered by time.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Script {
+    steps: Vec<Step>,
+}
+
+/// Line of a scenario script that cannot be parsed.
+#[derive(Debug)]
+pub struct ScriptError {
+    /// Line number, starting from 1 (0 if the file could not be read).
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ScriptError {}
+
+impl From<io::Error> for ScriptError {
+    fn from(err: io::Error) -> Self {
+        Self {
+            line: 0,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl Script {
+    /// Create a script from steps in any order.
+    #[must_use]
+    pub fn new(mut steps: Vec<Step>) -> Self {
+        // Stable, so steps at the same time keep their order
+        steps.sort_by_key(|step| step.at);
+        Self { steps }
+    }
+
+    /// Load a script file in the format of [`Script::parse`].
+    /// # Errors
+    /// If the file cannot be read or parsed.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ScriptError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse a script with one step per line, in the form `<milliseconds> <action> <arguments>`:
+    /// ```text
+    /// # comment
+    /// 0    send 1 21 3      # 3 fragments from node 1 to node 21
+    /// 100  pdr 11 0.5
+    /// 200  remove-link 11 12
+    /// 300  add-link 11 13
+    /// 400  crash 12
+    /// ```
+    /// # Errors
+    /// The first line that is not a valid step.
+    pub fn parse(text: &str) -> Result<Self, ScriptError> {
+        let steps = text
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let line = line.split('#').next().unwrap_or_default();
+                let tokens = line.split_whitespace().collect::<Vec<_>>();
+                (!tokens.is_empty()).then(|| {
+                    parse_step(&tokens).map_err(|message| ScriptError {
+                        line: i + 1,
+                        message,
+                    })
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self::new(steps))
+    }
+
+    #[must_use]
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+}
+
+fn parse_step(tokens: &[&str]) -> Result<Step, String> {
+    let at = Duration::from_millis(argument(tokens, 0)?);
+    let action = match tokens.get(1).copied() {
+        Some("crash") => {
+            expect_len(tokens, 3)?;
+            Action::Crash(argument(tokens, 2)?)
+        }
+        Some("pdr") => {
+            expect_len(tokens, 4)?;
+            Action::SetPdr(argument(tokens, 2)?, argument(tokens, 3)?)
+        }
+        Some("add-link") => {
+            expect_len(tokens, 4)?;
+            Action::AddLink(argument(tokens, 2)?, argument(tokens, 3)?)
+        }
+        Some("remove-link") => {
+            expect_len(tokens, 4)?;
+            Action::RemoveLink(argument(tokens, 2)?, argument(tokens, 3)?)
+        }
+        Some("send") => {
+            if tokens.len() != 4 {
+                expect_len(tokens, 5)?;
+            }
+            Action::Send {
+                from: argument(tokens, 2)?,
+                to: argument(tokens, 3)?,
+                fragments: if tokens.len() == 5 {
+                    argument(tokens, 4)?
+                } else {
+                    1
+                },
+            }
+        }
+        Some(other) => return Err(format!("unknown action `{other}`")),
+        None => return Err("missing action".to_string()),
+    };
+
+    Ok(Step { at, action })
+}
+
+fn expect_len(tokens: &[&str], len: usize) -> Result<(), String> {
+    if tokens.len() == len {
+        Ok(())
+    } else {
+        Err(format!(
+            "`{}` takes {} arguments, found {}",
+            tokens[1],
+            len - 2,
+            tokens.len() - 2
+        ))
+    }
+}
+
+fn argument<T: FromStr>(tokens: &[&str], index: usize) -> Result<T, String> {
+    let token = tokens.get(index).copied().unwrap_or_default();
+    token
+        .parse()
+        .map_err(|_| format!("invalid value `{token}`"))
+}
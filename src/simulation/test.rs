@@ -0,0 +1,1184 @@
+#![cfg(test)]
+
+use crate::drone::PacketKind;
+use crate::simulation::{
+    load_records, parse_config, read_records, validate_config, Action, ChromeTrace, Controller,
+    DotGraph, EventKind, EventRecord, GenerateError, Journeys, LoggedEvent, Network,
+    NetworkConfigError, NetworkGenerator, NodeKind, RecordError, RecordedNack, Recorder, Scenario,
+    ScenarioError, Script, Shape, SimulationError, Step, Topology,
+};
+use crossbeam_channel::unbounded;
+use rusty_tester::utils::data::{new_flood_request, new_forwarded, new_test_fragment_packet};
+use std::time::Duration;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, Nack, NackType, NodeType, Packet, PacketType, FRAGMENT_DSIZE};
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Client 1 - drone 11 - drone 12 - server 21.
+fn chain() -> Topology {
+    let mut topology = Topology::new();
+    topology
+        .add_client(1)
+        .add_drone(11, 0.0)
+        .add_drone(12, 0.0)
+        .add_server(21)
+        .connect(1, 11)
+        .connect(11, 12)
+        .connect(12, 21);
+    topology
+}
+
+#[test]
+fn test_topology_from_adjacency() {
+    let topology = Topology::from_adjacency(
+        [
+            (1, NodeKind::Client),
+            (11, NodeKind::Drone { pdr: 0.0 }),
+            (21, NodeKind::Server),
+        ],
+        [(11, vec![1, 21])],
+    );
+
+    assert!(topology.are_connected(1, 11));
+    assert!(topology.are_connected(21, 11));
+    assert!(!topology.are_connected(1, 21));
+    assert_eq!(topology.edges().collect::<Vec<_>>(), [(1, 11), (11, 21)]);
+}
+
+#[test]
+fn test_topology_shortest_path_only_through_drones() {
+    let mut topology = chain();
+    topology.add_client(2).connect(1, 2).connect(2, 21);
+
+    assert_eq!(topology.shortest_path(1, 21), Some(vec![1, 11, 12, 21]));
+    topology.disconnect(11, 12);
+    assert_eq!(topology.shortest_path(1, 21), None);
+}
+
+#[test]
+fn test_network_delivers_fragment() {
+    let network = Network::spawn(&chain()).unwrap();
+    let packet = new_test_fragment_packet(&[1, 11, 12, 21], 5);
+
+    network.send_from(1, packet.clone()).unwrap();
+
+    let received = network
+        .endpoint(21)
+        .unwrap()
+        .packet_recv()
+        .recv_timeout(TIMEOUT)
+        .unwrap();
+    assert_eq!(received, new_forwarded(&new_forwarded(&packet)));
+
+    let stats = network.stats(12).unwrap();
+    assert_eq!(stats.sent_to.get(&21), Some(&1));
+    network.join();
+}
+
+#[test]
+fn test_network_events() {
+    let network = Network::spawn(&chain()).unwrap();
+    let packet = new_test_fragment_packet(&[1, 11, 12, 21], 5);
+
+    network.send_from(1, packet.clone()).unwrap();
+
+    // Events of different drones can be received in any order
+    let mut events = [
+        network.next_event(TIMEOUT).unwrap(),
+        network.next_event(TIMEOUT).unwrap(),
+    ];
+    events.sort_by_key(|(id, _)| *id);
+    assert_eq!(events[0].0, 11);
+    assert!(matches!(events[0].1, DroneEvent::PacketSent(ref p) if *p == new_forwarded(&packet)));
+    assert_eq!(events[1].0, 12);
+    assert!(network.next_event(TIMEOUT / 4).is_none());
+}
+
+#[test]
+fn test_network_crash_nacks() {
+    let mut network = Network::spawn(&chain()).unwrap();
+    network.crash(12).unwrap();
+    assert!(!network.topology().are_connected(11, 12));
+
+    let packet = new_test_fragment_packet(&[1, 11, 12, 21], 5);
+    network.send_from(1, packet).unwrap();
+
+    let nack = network
+        .endpoint(1)
+        .unwrap()
+        .packet_recv()
+        .recv_timeout(TIMEOUT)
+        .unwrap();
+    assert!(matches!(
+        nack.pack_type,
+        PacketType::Nack(ref n) if n.nack_type == NackType::ErrorInRouting(12)
+    ));
+    assert_eq!(network.crash(12), Err(SimulationError::Stopped(12)));
+}
+
+#[test]
+fn test_network_links() {
+    let mut network = Network::spawn(&chain()).unwrap();
+
+    assert_eq!(
+        network.remove_link(1, 12),
+        Err(SimulationError::NotConnected(1, 12))
+    );
+    network.remove_link(11, 12).unwrap();
+    network.add_link(1, 12).unwrap();
+
+    let packet = new_test_fragment_packet(&[1, 12, 21], 5);
+    network.send_from(1, packet).unwrap();
+    assert!(network
+        .endpoint(21)
+        .unwrap()
+        .packet_recv()
+        .recv_timeout(TIMEOUT)
+        .is_ok());
+
+    let packet = new_test_fragment_packet(&[1, 11, 12, 21], 5);
+    network.send_from(1, packet).unwrap();
+    assert!(network
+        .endpoint(1)
+        .unwrap()
+        .packet_recv()
+        .recv_timeout(TIMEOUT)
+        .is_ok());
+}
+
+#[test]
+fn test_network_errors() {
+    let network = Network::spawn(&chain()).unwrap();
+    let packet = new_test_fragment_packet(&[1, 12, 21], 5);
+
+    assert_eq!(
+        network.send_from(1, packet.clone()),
+        Err(SimulationError::NotConnected(1, 12))
+    );
+    assert_eq!(
+        network.send_from(11, packet),
+        Err(SimulationError::NotAnEndpoint(11))
+    );
+    assert_eq!(network.set_pdr(1, 0.5), Err(SimulationError::NotADrone(1)));
+    assert!(matches!(
+        network.stats(99),
+        Err(SimulationError::UnknownNode(99))
+    ));
+}
+
+const CONFIG: &str = r"
+[[drone]]
+id = 11
+connected_node_ids = [12, 13, 1]
+pdr = 0.0
+
+[[drone]]
+id = 12
+connected_node_ids = [11, 13, 21]
+pdr = 0.0
+
+[[drone]]
+id = 13
+connected_node_ids = [11, 12, 21]
+pdr = 0.0
+
+[[client]]
+id = 1
+connected_drone_ids = [11]
+
+[[server]]
+id = 21
+connected_drone_ids = [12, 13]
+";
+
+#[test]
+fn test_config_network() {
+    let network = Network::from_config(&parse_config(CONFIG).unwrap()).unwrap();
+
+    assert_eq!(network.drone_ids().collect::<Vec<_>>(), [11, 12, 13]);
+    assert!(network.topology().are_connected(13, 21));
+    assert_eq!(network.endpoint(21).unwrap().node_type(), NodeType::Server);
+
+    let packet = new_test_fragment_packet(&[1, 11, 13, 21], 5);
+    network.send_from(1, packet).unwrap();
+    assert!(network
+        .endpoint(21)
+        .unwrap()
+        .packet_recv()
+        .recv_timeout(TIMEOUT)
+        .is_ok());
+}
+
+#[test]
+fn test_config_validation() {
+    let invalid = |from: &str, to: &str| parse_config(&CONFIG.replacen(from, to, 1)).unwrap_err();
+
+    assert!(matches!(
+        invalid("id = 13", "id = 12"),
+        NetworkConfigError::DuplicateId(12)
+    ));
+    assert!(matches!(
+        invalid("[12, 13, 1]", "[11, 12, 13, 1]"),
+        NetworkConfigError::SelfLoop(11)
+    ));
+    assert!(matches!(
+        invalid("[12, 13, 1]", "[12, 13, 1, 99]"),
+        NetworkConfigError::UnknownNeighbor {
+            node: 11,
+            neighbor: 99
+        }
+    ));
+    assert!(matches!(
+        invalid("[11, 13, 21]", "[11, 21]"),
+        NetworkConfigError::NotBidirectional {
+            node: 13,
+            neighbor: 12
+        }
+    ));
+    assert!(matches!(
+        invalid("[12, 13]", "[13]"),
+        NetworkConfigError::NotBidirectional {
+            node: 12,
+            neighbor: 21
+        }
+    ));
+    assert!(matches!(
+        invalid("pdr = 0.0", "pdr = "),
+        NetworkConfigError::Parse(_)
+    ));
+    for pdr in ["1.5", "-0.1", "nan"] {
+        assert!(matches!(
+            invalid("pdr = 0.0", &format!("pdr = {pdr}")),
+            NetworkConfigError::InvalidPdr { drone: 11, .. }
+        ));
+    }
+    assert!(matches!(
+        invalid("[12, 13, 1]", "[12, 13, 12, 1]"),
+        NetworkConfigError::DuplicateNeighbor {
+            node: 11,
+            neighbor: 12
+        }
+    ));
+}
+
+#[test]
+fn test_config_connection_limits() {
+    let mut config = parse_config(CONFIG).unwrap();
+    config.client[0].connected_drone_ids = vec![];
+    assert!(matches!(
+        validate_config(&config),
+        Err(NetworkConfigError::ClientConnections {
+            client: 1,
+            count: 0
+        })
+    ));
+
+    let mut config = parse_config(CONFIG).unwrap();
+    config.server[0].connected_drone_ids = vec![12];
+    config.drone[2].connected_node_ids = vec![11, 12];
+    assert!(matches!(
+        validate_config(&config),
+        Err(NetworkConfigError::ServerConnections {
+            server: 21,
+            count: 1
+        })
+    ));
+
+    // Listing the only drone three times is still one connection, but it is rejected
+    let mut config = parse_config(CONFIG).unwrap();
+    config.client[0].connected_drone_ids = vec![11, 11, 11];
+    assert!(matches!(
+        validate_config(&config),
+        Err(NetworkConfigError::DuplicateNeighbor {
+            node: 1,
+            neighbor: 11
+        })
+    ));
+
+    let mut config = parse_config(CONFIG).unwrap();
+    config.client[0].connected_drone_ids = vec![11, 21];
+    config.server[0].connected_drone_ids = vec![1, 12, 13];
+    assert!(matches!(
+        validate_config(&config),
+        Err(NetworkConfigError::NotConnectedToDrone {
+            node: 1,
+            neighbor: 21
+        })
+    ));
+}
+
+#[test]
+fn test_script_parse() {
+    let script = Script::parse(
+        "# comment\n\
+         200 crash 12\n\
+         \n\
+         0 send 1 21 3 # three fragments\n\
+         100 pdr 11 0.5\n\
+         100 add-link 1 12\n\
+         150 remove-link 11 12\n\
+         0 send 1 21\n",
+    )
+    .unwrap();
+
+    let actions = script
+        .steps()
+        .iter()
+        .map(|step| (step.at.as_millis(), step.action.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        actions,
+        [
+            (
+                0,
+                Action::Send {
+                    from: 1,
+                    to: 21,
+                    fragments: 3
+                }
+            ),
+            (
+                0,
+                Action::Send {
+                    from: 1,
+                    to: 21,
+                    fragments: 1
+                }
+            ),
+            (100, Action::SetPdr(11, 0.5)),
+            (100, Action::AddLink(1, 12)),
+            (150, Action::RemoveLink(11, 12)),
+            (200, Action::Crash(12)),
+        ]
+    );
+}
+
+#[test]
+fn test_script_parse_errors() {
+    let error = |text| Script::parse(text).unwrap_err();
+
+    assert_eq!(error("0 crash 1\nten crash 2").line, 2);
+    assert_eq!(error("0 explode 1").message, "unknown action `explode`");
+    assert_eq!(
+        error("0 crash").message,
+        "`crash` takes 1 arguments, found 0"
+    );
+    assert_eq!(error("0 pdr 1 high").message, "invalid value `high`");
+    assert_eq!(error("0 crash 300").line, 1);
+}
+
+#[test]
+fn test_controller_summary() {
+    let script =
+        Script::parse("0 send 1 21 4\n20 pdr 11 1.0\n20 send 1 21 2\n40 crash 12\n40 send 1 21")
+            .unwrap();
+    let network = Network::spawn_with(&chain(), |_, builder| builder.simulation_seed(7)).unwrap();
+
+    let mut controller = Controller::new(network);
+    controller.run_script(&script, TIMEOUT / 2).unwrap();
+    let summary = controller.summary();
+
+    assert_eq!(summary.fragments_sent, 6);
+    assert_eq!(summary.fragments_delivered, 4);
+    assert_eq!(summary.fragments_unroutable, 1);
+    assert_eq!(summary.drops, 2);
+    assert_eq!(summary.nacks, 2);
+    // The nacks are sent back to the client without shortcuts
+    assert_eq!(summary.shortcuts, 0);
+    assert!((summary.delivery_ratio() - 4.0 / 6.0).abs() < f64::EPSILON);
+
+    let nacks_received = controller
+        .received()
+        .iter()
+        .filter(|(id, p)| *id == 1 && matches!(p.pack_type, PacketType::Nack(_)))
+        .count();
+    assert_eq!(nacks_received, 2);
+    assert!(controller
+        .events()
+        .iter()
+        .all(|e| e.drone == 11 || e.drone == 12));
+}
+
+#[test]
+fn test_controller_delivers_shortcuts() {
+    let network = Network::spawn(&chain()).unwrap();
+    let mut controller = Controller::new(network);
+
+    // Ack toward the client that must be shortcut when 11 is removed from the route
+    let ack = Packet::new_ack(SourceRoutingHeader::new(vec![21, 12, 11, 1], 1), 3, 0);
+    controller.apply(&Action::RemoveLink(11, 12)).unwrap();
+    controller.network().send_from(21, ack.clone()).unwrap();
+    controller.run_until_idle(TIMEOUT / 2);
+
+    assert_eq!(controller.summary().shortcuts, 1);
+    assert!(controller
+        .received()
+        .iter()
+        .any(|(id, p)| *id == 1 && p.pack_type == ack.pack_type));
+}
+
+#[test]
+fn test_controller_script_error() {
+    let script = Script::parse("0 crash 1").unwrap();
+    let mut controller = Controller::new(Network::spawn(&chain()).unwrap());
+
+    assert_eq!(
+        controller.run_script(&script, Duration::ZERO),
+        Err(SimulationError::NotADrone(1))
+    );
+
+    let script = Script::new(vec![Step {
+        at: Duration::MAX,
+        action: Action::Crash(11),
+    }]);
+    assert_eq!(
+        controller.run_script(&script, Duration::ZERO),
+        Err(SimulationError::TooLate(Duration::MAX))
+    );
+}
+
+const SCENARIO: &str = r#"
+seed = 7
+settle_ms = 100
+
+[[flow]]
+from = 1
+to = 21
+fragments = 4
+
+[[flow]]
+at_ms = 40
+from = 1
+to = 21
+messages = 2
+interval_ms = 10
+
+[[command]]
+at_ms = 20
+action = "pdr"
+drone = 11
+pdr = 1.0
+
+[[expect]]
+type = "delivered"
+from = 1
+to = 21
+at_least = 4
+
+[[expect]]
+type = "delivered"
+from = 1
+to = 21
+
+[[expect]]
+type = "dropped"
+drone = 11
+at_least = 2
+
+[[expect]]
+type = "nacked"
+drone = 12
+
+[[expect]]
+type = "delivery-ratio"
+at_least = 0.5
+"#;
+
+#[test]
+fn test_scenario_script() {
+    let scenario = Scenario::parse(&format!("{SCENARIO}{CONFIG}")).unwrap();
+
+    let actions = scenario
+        .script()
+        .unwrap()
+        .steps()
+        .iter()
+        .map(|step| (step.at.as_millis(), step.action.clone()))
+        .collect::<Vec<_>>();
+    let send = Action::Send {
+        from: 1,
+        to: 21,
+        fragments: 1,
+    };
+    assert_eq!(
+        actions,
+        [
+            (
+                0,
+                Action::Send {
+                    from: 1,
+                    to: 21,
+                    fragments: 4
+                }
+            ),
+            (20, Action::SetPdr(11, 1.0)),
+            (40, send.clone()),
+            (50, send),
+        ]
+    );
+}
+
+#[test]
+fn test_scenario_run() {
+    let scenario = Scenario::parse(&format!("{SCENARIO}{CONFIG}")).unwrap();
+    let report = scenario.run().unwrap();
+
+    let passed = report.results.iter().map(|r| r.passed).collect::<Vec<_>>();
+    assert_eq!(passed, [true, false, true, false, true]);
+    assert_eq!(report.results[1].observed, "4 delivered");
+    assert!(!report.passed());
+    assert_eq!(report.summary.fragments_sent, 6);
+}
+
+#[test]
+fn test_scenario_network_file() {
+    let dir = std::env::temp_dir().join(format!("rusty_scenario_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("network.toml"), CONFIG).unwrap();
+    std::fs::write(
+        dir.join("scenario.toml"),
+        "network = \"network.toml\"\n[[expect]]\ntype = \"not-delivered\"\nfrom = 1\nto = 21\n",
+    )
+    .unwrap();
+
+    let scenario = Scenario::from_file(dir.join("scenario.toml"));
+    std::fs::remove_dir_all(&dir).unwrap();
+    let scenario = scenario.unwrap();
+
+    assert_eq!(scenario.drone.len(), 3);
+    assert!(scenario.run().unwrap().passed());
+}
+
+#[test]
+fn test_scenario_errors() {
+    assert!(matches!(
+        Scenario::parse(&format!("network = \"network.toml\"\n{CONFIG}")),
+        Err(ScenarioError::TwoTopologies)
+    ));
+    assert!(matches!(
+        Scenario::parse("[[expect]]\ntype = \"teleported\"\n"),
+        Err(ScenarioError::Parse(_))
+    ));
+    assert!(matches!(
+        Scenario::parse(&CONFIG.replacen("[12, 13]", "[13]", 1)),
+        Err(ScenarioError::Network(_))
+    ));
+
+    assert!(matches!(
+        Scenario::parse(&format!(
+            "[[flow]]\nfrom = 1\nto = 21\nmessages = 4\ninterval_ms = {}\n{CONFIG}",
+            i64::MAX
+        )),
+        Err(ScenarioError::FlowTooLong { from: 1, to: 21 })
+    ));
+    let mut scenario = Scenario::parse(&format!("[[flow]]\nfrom = 1\nto = 21\n{CONFIG}")).unwrap();
+    scenario.flow[0].at_ms = u64::MAX;
+    scenario.flow[0].messages = 2;
+    scenario.flow[0].interval_ms = 1;
+    assert!(matches!(
+        scenario.script(),
+        Err(ScenarioError::FlowTooLong { from: 1, to: 21 })
+    ));
+
+    // Mistyped keys of a command
+    for command in ["drnoe = 12", "drone = 12\nat = 5"] {
+        assert!(matches!(
+            Scenario::parse(&format!(
+                "[[command]]\naction = \"crash\"\n{command}\n{CONFIG}"
+            )),
+            Err(ScenarioError::Parse(_))
+        ));
+    }
+
+    let scenario = Scenario::parse(&format!(
+        "[[command]]\naction = \"crash\"\ndrone = 1\n{CONFIG}"
+    ))
+    .unwrap();
+    assert!(matches!(
+        scenario.run(),
+        Err(ScenarioError::Simulation(SimulationError::NotADrone(1)))
+    ));
+}
+
+const SHAPES: [Shape; 6] = [
+    Shape::Ring { drones: 12 },
+    Shape::Grid {
+        width: 4,
+        height: 3,
+    },
+    Shape::RandomGeometric {
+        drones: 30,
+        radius: 0.2,
+    },
+    Shape::ErdosRenyi {
+        drones: 30,
+        p: 0.05,
+    },
+    Shape::BarabasiAlbert { drones: 30, m: 2 },
+    Shape::DoubleChain { length: 6 },
+];
+
+/// Every drone can reach every other one through drones only.
+fn drones_connected(topology: &Topology) -> bool {
+    let drones = topology
+        .nodes()
+        .filter(|(_, kind)| matches!(kind, NodeKind::Drone { .. }))
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    drones
+        .iter()
+        .all(|&id| topology.shortest_path(drones[0], id).is_some())
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn test_generate_valid_networks() {
+    for shape in SHAPES {
+        for seed in 0..5 {
+            let config = NetworkGenerator::new(shape)
+                .clients(3)
+                .servers(2)
+                .pdr(0.1)
+                .seed(seed)
+                .generate()
+                .unwrap();
+
+            assert!(validate_config(&config).is_ok(), "{shape:?} seed {seed}");
+            assert_eq!(config.client.len(), 3);
+            assert_eq!(config.server.len(), 2);
+            assert!(config.drone.iter().all(|d| d.pdr == 0.1));
+            assert!(
+                drones_connected(&Topology::from_config(&config)),
+                "{shape:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_generate_shapes() {
+    let edges = |shape| {
+        let config = NetworkGenerator::new(shape).generate().unwrap();
+        Topology::from_config(&config).edges().collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        edges(Shape::Ring { drones: 4 }),
+        [(0, 1), (0, 3), (1, 2), (2, 3)]
+    );
+    assert_eq!(
+        edges(Shape::Grid {
+            width: 4,
+            height: 3
+        })
+        .len(),
+        17
+    );
+    assert_eq!(
+        edges(Shape::DoubleChain { length: 3 }),
+        [(0, 1), (0, 3), (1, 2), (1, 4), (2, 5), (3, 4), (4, 5)]
+    );
+    // Complete graph, or only the links joining the components
+    assert_eq!(edges(Shape::ErdosRenyi { drones: 6, p: 1.0 }).len(), 15);
+    assert_eq!(edges(Shape::ErdosRenyi { drones: 6, p: 0.0 }).len(), 5);
+    assert_eq!(
+        edges(Shape::RandomGeometric {
+            drones: 6,
+            radius: 2.0
+        })
+        .len(),
+        15
+    );
+
+    let config = NetworkGenerator::new(Shape::BarabasiAlbert { drones: 50, m: 3 })
+        .generate()
+        .unwrap();
+    assert!(config.drone.iter().all(|d| d.connected_node_ids.len() >= 3));
+}
+
+#[test]
+fn test_generate_reproducible() {
+    let generate = |seed| {
+        let config = NetworkGenerator::new(SHAPES[2])
+            .clients(2)
+            .servers(2)
+            .seed(seed)
+            .generate()
+            .unwrap();
+        format!("{config:?}")
+    };
+
+    assert_eq!(generate(3), generate(3));
+    assert_ne!(generate(3), generate(4));
+}
+
+#[test]
+fn test_generate_errors() {
+    let generate = |shape, clients, servers| {
+        NetworkGenerator::new(shape)
+            .clients(clients)
+            .servers(servers)
+            .generate()
+            .unwrap_err()
+    };
+
+    assert_eq!(
+        generate(Shape::Ring { drones: 250 }, 5, 2),
+        GenerateError::TooManyNodes(257)
+    );
+    let huge = [
+        generate(
+            Shape::Grid {
+                width: usize::MAX,
+                height: 2,
+            },
+            0,
+            0,
+        ),
+        generate(Shape::DoubleChain { length: usize::MAX }, 0, 0),
+        generate(Shape::Ring { drones: 10 }, usize::MAX, 1),
+    ];
+    assert!(huge
+        .iter()
+        .all(|err| *err == GenerateError::TooManyNodes(usize::MAX)));
+    assert_eq!(
+        generate(Shape::Ring { drones: 1 }, 1, 1),
+        GenerateError::TooFewDrones(1)
+    );
+    assert_eq!(
+        generate(Shape::BarabasiAlbert { drones: 3, m: 3 }, 0, 0),
+        GenerateError::InvalidParameter("m")
+    );
+    assert_eq!(
+        generate(Shape::ErdosRenyi { drones: 3, p: 1.5 }, 0, 0),
+        GenerateError::InvalidParameter("p")
+    );
+}
+
+#[test]
+fn test_generate_large_network_runs() {
+    let config = NetworkGenerator::new(Shape::BarabasiAlbert { drones: 240, m: 2 })
+        .clients(8)
+        .servers(8)
+        .seed(1)
+        .generate()
+        .unwrap();
+    let network = Network::from_config(&config).unwrap();
+
+    let client = config.client[0].id;
+    let server = config.server[7].id;
+    let path = network.topology().shortest_path(client, server).unwrap();
+    let packet = new_test_fragment_packet(&path, 1);
+    network.send_from(client, packet).unwrap();
+
+    let received = network
+        .endpoint(server)
+        .unwrap()
+        .packet_recv()
+        .recv_timeout(TIMEOUT)
+        .unwrap();
+    assert_eq!(received.routing_header.hop_index, path.len() - 1);
+    network.join();
+}
+
+#[test]
+fn test_dot_topology() {
+    let dot = DotGraph::from_topology(&chain()).to_dot();
+
+    assert_eq!(
+        dot,
+        "graph network {\n    \
+             1 [shape=box];\n    \
+             11 [shape=circle];\n    \
+             12 [shape=circle];\n    \
+             21 [shape=box3d];\n    \
+             1 -- 11;\n    \
+             11 -- 12;\n    \
+             12 -- 21;\n\
+         }"
+    );
+}
+
+#[test]
+fn test_dot_network_links() {
+    let mut topology = chain();
+    topology.add_drone(13, 0.0).connect(11, 13).connect(13, 21);
+    let mut network = Network::spawn(&topology).unwrap();
+    network.remove_link(13, 21).unwrap();
+    network.crash(12).unwrap();
+
+    let dot = DotGraph::from_network(&network).to_dot();
+    assert!(dot.contains("    12 [shape=circle, style=dashed, color=gray];\n"));
+    assert!(dot.contains("    1 -- 11;\n"));
+    assert!(dot.contains("    11 -- 13;\n"));
+    assert!(!dot.contains("13 -- 21"));
+    assert!(!dot.contains("11 -- 12"));
+    assert!(!dot.contains("12 -- 21"));
+    network.join();
+}
+
+#[test]
+fn test_dot_overlays() {
+    let mut topology = chain();
+    topology
+        .add_drone(13, 0.0)
+        .connect(11, 13)
+        .connect(12, 13)
+        .connect(13, 21);
+    let network = Network::spawn(&topology).unwrap();
+    network
+        .inject(11, new_flood_request(7, 3, 1, true))
+        .unwrap();
+
+    let client = network.endpoint(1).unwrap().packet_recv();
+    let mut graph = DotGraph::from_network(&network);
+    while let Ok(packet) = client.recv_timeout(TIMEOUT) {
+        if let PacketType::FloodResponse(ref response) = packet.pack_type {
+            graph.flood_response(response);
+        }
+    }
+    // Drone 11 sent the fragment to 13, then 13 sent it to the server
+    let sent = |drone, hop_index| LoggedEvent {
+        at: Duration::ZERO,
+        drone,
+        event: DroneEvent::PacketSent(Packet {
+            routing_header: SourceRoutingHeader::new(vec![1, 11, 13, 21], hop_index),
+            ..new_test_fragment_packet(&[1, 11, 13, 21], 2)
+        }),
+    };
+    let other = LoggedEvent {
+        event: DroneEvent::PacketSent(new_test_fragment_packet(&[1, 11, 12, 21], 3)),
+        ..sent(11, 2)
+    };
+    graph.journey(&[sent(13, 3), other, sent(11, 2)], 2, 1);
+
+    let paths = graph
+        .overlays()
+        .iter()
+        .map(|overlay| overlay.path.clone())
+        .collect::<Vec<_>>();
+    // Which drones terminate the flood depends on timing
+    let (journey, floods) = paths.split_last().unwrap();
+    assert!(!floods.is_empty());
+    assert!(floods.iter().all(|path| path.starts_with(&[1, 11])));
+    assert_eq!(journey, &[11, 13, 21]);
+
+    let color = &graph.overlays().last().unwrap().color;
+    let dot = graph.to_dot();
+    assert!(dot.contains(&format!(
+        "    11 -- 13 [color={color}, penwidth=2, dir=forward, constraint=false];\n"
+    )));
+    assert!(dot.contains(&format!("{color}: session 2 fragment 1\\l")));
+}
+
+#[test]
+fn test_record_summary() {
+    let nack = Packet::new_nack(
+        SourceRoutingHeader::new(vec![12, 11, 1], 1),
+        5,
+        Nack {
+            fragment_index: 3,
+            nack_type: NackType::ErrorInRouting(21),
+        },
+    );
+    let logged = LoggedEvent {
+        at: Duration::from_micros(1500),
+        drone: 11,
+        event: DroneEvent::PacketDropped(nack),
+    };
+
+    let record = EventRecord::from(&logged);
+    assert_eq!(record.at(), Duration::from_micros(1500));
+    assert_eq!(record.drone, 11);
+    assert_eq!(record.event, EventKind::PacketDropped);
+    assert_eq!(record.packet.kind, PacketKind::Nack);
+    assert_eq!(record.packet.session_id, 5);
+    assert_eq!(record.packet.fragment_index, Some(3));
+    assert_eq!(
+        record.packet.nack_type,
+        Some(RecordedNack::ErrorInRouting(21))
+    );
+    assert_eq!(record.packet.hops, vec![12, 11, 1]);
+    assert_eq!(record.packet.hop_index, 1);
+
+    let line = serde_json::to_string(&record).unwrap();
+    assert!(!line.contains("flood_id"));
+    assert_eq!(read_records(line.as_bytes()).unwrap(), vec![record]);
+}
+
+#[test]
+fn test_record_controller() {
+    let path = std::env::temp_dir().join(format!("rusty_record_{}.jsonl", std::process::id()));
+    let script = Script::parse("0 send 1 21 2\n10 pdr 12 1.0\n10 send 1 21").unwrap();
+    let mut controller = Controller::new(Network::spawn(&chain()).unwrap());
+
+    controller.record(Recorder::create(&path).unwrap());
+    controller.run_script(&script, TIMEOUT / 2).unwrap();
+    controller.take_recorder().unwrap().finish().unwrap();
+    let records = load_records(&path);
+    std::fs::remove_file(&path).unwrap();
+    let records = records.unwrap();
+
+    let expected = controller
+        .events()
+        .iter()
+        .map(EventRecord::from)
+        .collect::<Vec<_>>();
+    assert_eq!(records, expected);
+    assert!(records.windows(2).all(|r| r[0].at_ns <= r[1].at_ns));
+    // The first two fragments went through 11 and 12, the last one was dropped by 12
+    let fragments_sent = records
+        .iter()
+        .filter(|r| r.event == EventKind::PacketSent && r.packet.kind == PacketKind::MsgFragment)
+        .count();
+    assert_eq!(fragments_sent, 5);
+    assert!(records
+        .iter()
+        .any(|r| r.drone == 12 && r.event == EventKind::PacketDropped));
+    assert!(records.iter().any(|r| r.drone == 12
+        && r.packet.kind == PacketKind::Nack
+        && r.packet.nack_type == Some(RecordedNack::Dropped)));
+}
+
+#[test]
+fn test_record_errors() {
+    let log = "\n{\"at_ns\":0,\"drone\":1}\n";
+    assert!(matches!(
+        read_records(log.as_bytes()),
+        Err(RecordError::Parse { line: 2, .. })
+    ));
+    assert!(read_records(&b"\n\n"[..]).unwrap().is_empty());
+    assert!(matches!(
+        load_records("/nonexistent/rusty_record.jsonl"),
+        Err(RecordError::Io(_))
+    ));
+}
+
+/// Event of fragment `index` of session 0 on the chain, sent by the drone at `hop_index - 1`.
+fn fragment_event(at_ms: u64, index: u64, hop_index: usize, dropped: bool) -> EventRecord {
+    let packet = Packet::new_fragment(
+        SourceRoutingHeader::new(vec![1, 11, 12, 21], hop_index),
+        0,
+        Fragment {
+            fragment_index: index,
+            total_n_fragments: 3,
+            length: 0,
+            data: [0; FRAGMENT_DSIZE],
+        },
+    );
+    let drone = packet.routing_header.hops[hop_index - usize::from(!dropped)];
+    let event = if dropped {
+        DroneEvent::PacketDropped(packet)
+    } else {
+        DroneEvent::PacketSent(packet)
+    };
+    EventRecord::new(Duration::from_millis(at_ms), drone, &event)
+}
+
+/// Event of a nack for fragment `index` of session 0, sent by `drone`.
+fn nack_event(
+    at_ms: u64,
+    drone: NodeId,
+    hops: &[NodeId],
+    index: u64,
+    shortcut: bool,
+) -> EventRecord {
+    let nack_type = if hops[0] == 12 {
+        NackType::Dropped
+    } else {
+        NackType::ErrorInRouting(12)
+    };
+    let packet = Packet::new_nack(
+        SourceRoutingHeader::new(hops.to_vec(), 1),
+        0,
+        Nack {
+            fragment_index: index,
+            nack_type,
+        },
+    );
+    let event = if shortcut {
+        DroneEvent::ControllerShortcut(packet)
+    } else {
+        DroneEvent::PacketSent(packet)
+    };
+    EventRecord::new(Duration::from_millis(at_ms), drone, &event)
+}
+
+#[test]
+fn test_journeys_from_records() {
+    let ack = Packet::new_ack(SourceRoutingHeader::new(vec![21, 12, 11, 1], 2), 0, 0);
+    let records = [
+        // Received out of order: delivered through 11 and 12
+        fragment_event(3, 0, 3, false),
+        fragment_event(1, 0, 2, false),
+        EventRecord::new(Duration::from_millis(4), 12, &DroneEvent::PacketSent(ack)),
+        // Dropped by 12, the nack is shortcut by 11
+        fragment_event(5, 1, 2, false),
+        fragment_event(6, 1, 2, true),
+        nack_event(6, 12, &[12, 11, 1], 1, false),
+        nack_event(7, 11, &[12, 11, 1], 1, true),
+        // Nacked by the first drone, so only the nack is seen
+        nack_event(8, 11, &[11, 1], 2, false),
+    ];
+
+    let journeys = Journeys::from_records(&records);
+    assert_eq!(journeys.len(), 3);
+
+    let delivered = journeys.get(0, 0).unwrap();
+    assert!(delivered.delivered);
+    assert_eq!(delivered.route, vec![1, 11, 12, 21]);
+    let hops = delivered
+        .hops
+        .iter()
+        .map(|hop| (hop.from, hop.to, hop.elapsed))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        hops,
+        vec![(11, 12, None), (12, 21, Some(Duration::from_millis(2)))]
+    );
+    assert_eq!(delivered.duration(), Duration::from_millis(2));
+    assert!(!delivered.shortcut());
+
+    let dropped = journeys.get(0, 1).unwrap();
+    assert!(!dropped.delivered);
+    assert_eq!(dropped.hops.len(), 1);
+    assert_eq!(dropped.dropped_at, Some(12));
+    assert_eq!(dropped.nacked_at, Some((12, RecordedNack::Dropped)));
+    assert_eq!(dropped.shortcuts, vec![11]);
+
+    let nacked = journeys.get(0, 2).unwrap();
+    assert!(nacked.hops.is_empty());
+    assert_eq!(nacked.route, vec![1, 11]);
+    assert_eq!(
+        nacked.nacked_at,
+        Some((11, RecordedNack::ErrorInRouting(12)))
+    );
+
+    assert_eq!(
+        journeys.to_string(),
+        "session 0 fragment 0: 11 -> 12 -> 21 (+2.000 ms), delivered\n\
+         session 0 fragment 1: 11 -> 12, dropped at 12, nacked at 12 (Dropped), shortcut at 11\n\
+         session 0 fragment 2: nacked at 11 (ErrorInRouting(12))"
+    );
+}
+
+#[test]
+fn test_journeys_controller() {
+    let script = Script::parse("0 send 1 21 2\n10 pdr 12 1.0\n10 send 1 21").unwrap();
+    let network = Network::spawn(&chain()).unwrap();
+    let mut controller = Controller::new(network);
+    controller.run_script(&script, TIMEOUT / 2).unwrap();
+
+    let records = controller
+        .events()
+        .iter()
+        .map(EventRecord::from)
+        .collect::<Vec<_>>();
+    let journeys = Journeys::from_records(&records);
+
+    assert_eq!(journeys.len(), 3);
+    for fragment_index in 0..2 {
+        let journey = journeys.get(0, fragment_index).unwrap();
+        assert!(journey.delivered);
+        let path = journey
+            .hops
+            .iter()
+            .map(|h| (h.from, h.to))
+            .collect::<Vec<_>>();
+        assert_eq!(path, vec![(11, 12), (12, 21)]);
+    }
+    let dropped = journeys.get(1, 0).unwrap();
+    assert!(!dropped.delivered);
+    assert_eq!(dropped.dropped_at, Some(12));
+    assert_eq!(dropped.nacked_at, Some((12, RecordedNack::Dropped)));
+}
+
+/// Events of the trace with the given phase, as (name, tid).
+fn trace_events(trace: &serde_json::Value, phase: &str) -> Vec<(String, u64)> {
+    trace["traceEvents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["ph"] == phase)
+        .map(|e| {
+            (
+                e["name"].as_str().unwrap().to_string(),
+                e["tid"].as_u64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_chrome_trace() {
+    let mut topology = chain();
+    topology.add_drone(12, 1.0);
+    let (span_send, span_recv) = unbounded();
+    let network =
+        Network::spawn_with(&topology, |_, builder| builder.span_send(span_send.clone())).unwrap();
+    let mut controller = Controller::new(network);
+    controller
+        .apply(&Action::Send {
+            from: 1,
+            to: 21,
+            fragments: 1,
+        })
+        .unwrap();
+    controller
+        .network()
+        .inject(11, new_flood_request(7, 3, 1, true))
+        .unwrap();
+    controller.run_until_idle(TIMEOUT / 2);
+
+    let mut trace = ChromeTrace::new();
+    trace
+        .add_spans(span_recv.try_iter())
+        .add_events(controller.events());
+    let json = trace.to_json();
+
+    let mut slices = trace_events(&json, "X");
+    slices.sort();
+    assert_eq!(
+        slices,
+        [
+            ("FloodRequest 3".to_string(), 11),
+            ("FloodRequest 3".to_string(), 12),
+            ("MsgFragment 0/1".to_string(), 11),
+            ("MsgFragment 0/1".to_string(), 12),
+            ("Nack 0".to_string(), 11),
+        ]
+    );
+
+    // Flows start from the sender and finish at the receiver
+    let mut flows = trace_events(&json, "s");
+    flows.extend(trace_events(&json, "f"));
+    flows.sort();
+    assert_eq!(
+        flows,
+        [
+            ("flood".to_string(), 11),
+            ("flood".to_string(), 12),
+            ("forward".to_string(), 11),
+            ("forward".to_string(), 12),
+            ("nack".to_string(), 11),
+            ("nack".to_string(), 12),
+        ]
+    );
+
+    let mut instants = trace_events(&json, "i");
+    instants.sort();
+    assert_eq!(
+        instants,
+        [("drop".to_string(), 12), ("nack Dropped".to_string(), 12)]
+    );
+
+    let mut written = vec![];
+    trace.write(&mut written).unwrap();
+    assert_eq!(written, serde_json::to_vec(&json).unwrap());
+    assert_eq!(
+        ChromeTrace::new().to_json()["traceEvents"],
+        serde_json::json!([])
+    );
+}
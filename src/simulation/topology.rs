@@ -0,0 +1,153 @@
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+
+/// What a node of the network is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeKind {
+    Drone { pdr: f32 },
+    Client,
+    Server,
+}
+
+impl NodeKind {
+    #[must_use]
+    pub fn node_type(&self) -> NodeType {
+        match self {
+            NodeKind::Drone { .. } => NodeType::Drone,
+            NodeKind::Client => NodeType::Client,
+            NodeKind::Server => NodeType::Server,
+        }
+    }
+}
+
+/// Nodes of a network and the (bidirectional) links between them.
+#[derive(Debug, Clone, Default)]
+pub struct Topology {
+    nodes: BTreeMap<NodeId, NodeKind>,
+    adjacency: BTreeMap<NodeId, BTreeSet<NodeId>>,
+}
+
+impl Topology {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add (or replace) a node.
+    pub fn add_node(&mut self, id: NodeId, kind: NodeKind) -> &mut Self {
+        self.nodes.insert(id, kind);
+        self.adjacency.entry(id).or_default();
+        self
+    }
+
+    pub fn add_drone(&mut self, id: NodeId, pdr: f32) -> &mut Self {
+        self.add_node(id, NodeKind::Drone { pdr })
+    }
+
+    pub fn add_client(&mut self, id: NodeId) -> &mut Self {
+        self.add_node(id, NodeKind::Client)
+    }
+
+    pub fn add_server(&mut self, id: NodeId) -> &mut Self {
+        self.add_node(id, NodeKind::Server)
+    }
+
+    /// Link two nodes in both directions.
+    pub fn connect(&mut self, a: NodeId, b: NodeId) -> &mut Self {
+        self.adjacency.entry(a).or_default().insert(b);
+        self.adjacency.entry(b).or_default().insert(a);
+        self
+    }
+
+    /// Remove the link between two nodes (in both directions).
+    pub fn disconnect(&mut self, a: NodeId, b: NodeId) -> &mut Self {
+        if let Some(neighbors) = self.adjacency.get_mut(&a) {
+            neighbors.remove(&b);
+        }
+        if let Some(neighbors) = self.adjacency.get_mut(&b) {
+            neighbors.remove(&a);
+        }
+        self
+    }
+
+    /// Build a topology from an adjacency list, every listed link is made bidirectional.
+    #[must_use]
+    pub fn from_adjacency(
+        nodes: impl IntoIterator<Item = (NodeId, NodeKind)>,
+        adjacency: impl IntoIterator<Item = (NodeId, Vec<NodeId>)>,
+    ) -> Self {
+        let mut topology = Self::new();
+        for (id, kind) in nodes {
+            topology.add_node(id, kind);
+        }
+        for (id, neighbors) in adjacency {
+            for neighbor in neighbors {
+                topology.connect(id, neighbor);
+            }
+        }
+        topology
+    }
+
+    #[must_use]
+    pub fn kind(&self, id: NodeId) -> Option<NodeKind> {
+        self.nodes.get(&id).copied()
+    }
+
+    /// All the nodes, ordered by id.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, NodeKind)> + '_ {
+        self.nodes.iter().map(|(id, kind)| (*id, *kind))
+    }
+
+    /// Nodes linked to `id`, ordered by id.
+    pub fn neighbors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.adjacency.get(&id).into_iter().flatten().copied()
+    }
+
+    #[must_use]
+    pub fn are_connected(&self, a: NodeId, b: NodeId) -> bool {
+        self.adjacency.get(&a).is_some_and(|n| n.contains(&b))
+    }
+
+    /// Every link once, with the smaller id first.
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId)> + '_ {
+        self.adjacency
+            .iter()
+            .flat_map(|(a, neighbors)| neighbors.iter().map(move |b| (*a, *b)))
+            .filter(|(a, b)| a < b)
+    }
+
+    /// Shortest path from `from` to `to` (both included) that only goes through drones.
+    #[must_use]
+    pub fn shortest_path(&self, from: NodeId, to: NodeId) -> Option<Vec<NodeId>> {
+        let mut previous = BTreeMap::from([(from, from)]);
+        let mut frontier = VecDeque::from([from]);
+
+        while let Some(node) = frontier.pop_front() {
+            if node == to {
+                let mut path = vec![to];
+                let mut current = to;
+                while current != from {
+                    current = previous[&current];
+                    path.push(current);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            // Only drones can forward packets
+            if node != from && !matches!(self.kind(node), Some(NodeKind::Drone { .. })) {
+                continue;
+            }
+
+            for neighbor in self.neighbors(node) {
+                if let Entry::Vacant(entry) = previous.entry(neighbor) {
+                    entry.insert(node);
+                    frontier.push_back(neighbor);
+                }
+            }
+        }
+        None
+    }
+}
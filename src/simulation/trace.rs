@@ -0,0 +1,282 @@
+use crate::drone::PacketSpan;
+use crate::simulation::{LoggedEvent, PacketSummary};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+
+/// Process id of every track, all the drones are threads of the same process.
+const PID: u64 = 1;
+
+/// Drone and packet of a span: the session and fragment index, or the flood id.
+/// A packet, its forwarded copy and the packets created in response to it share the packet part.
+type SpanKey = (NodeId, Option<u64>, u64);
+
+/// Timeline of the packets handled by the drones, in the Chrome Trace Event format
+/// that Perfetto and `chrome://tracing` can open.
+///
+/// Every drone is a track and every [`PacketSpan`] a slice on it. A packet handled by a drone
+/// after being forwarded (or created, like nacks and flood responses) by another one is linked
+/// to it with a flow arrow. Drops, nacks and shortcuts are instant events at the end of the
+/// slice of the packet that caused them.
+#[derive(Debug, Clone, Default)]
+pub struct ChromeTrace {
+    spans: Vec<PacketSpan>,
+    events: Vec<(NodeId, DroneEvent)>,
+}
+
+impl ChromeTrace {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the spans received from the drones.
+    pub fn add_spans(&mut self, spans: impl IntoIterator<Item = PacketSpan>) -> &mut Self {
+        self.spans.extend(spans);
+        self.spans.sort_by_key(|span| span.start);
+        self
+    }
+
+    /// Add the events of the drones, used for drops, nacks and shortcuts.
+    pub fn add_events<'a>(
+        &mut self,
+        events: impl IntoIterator<Item = &'a LoggedEvent>,
+    ) -> &mut Self {
+        self.events
+            .extend(events.into_iter().map(|e| (e.drone, e.event.clone())));
+        self
+    }
+
+    /// The trace as a JSON object.
+    #[must_use]
+    pub fn to_json(&self) -> Value {
+        let Some(origin) = self.spans.first().map(|span| span.start) else {
+            return json!({ "traceEvents": [] });
+        };
+        let ts = |instant: Instant| micros(instant.saturating_duration_since(origin));
+
+        let mut trace = vec![json!({
+            "name": "process_name", "ph": "M", "pid": PID, "args": { "name": "network" }
+        })];
+        let drones = self
+            .spans
+            .iter()
+            .map(|span| span.id)
+            .collect::<BTreeSet<_>>();
+        for id in drones {
+            trace.push(json!({
+                "name": "thread_name", "ph": "M", "pid": PID, "tid": id,
+                "args": { "name": format!("drone {id}") }
+            }));
+        }
+
+        for span in &self.spans {
+            trace.push(json!({
+                "name": span_name(&span.packet),
+                "cat": "packet",
+                "ph": "X",
+                "ts": ts(span.start),
+                "dur": micros(span.duration),
+                "pid": PID,
+                "tid": span.id,
+                "args": {
+                    "packet": PacketSummary::from(&span.packet),
+                    "crashing": span.crashing,
+                },
+            }));
+        }
+
+        for (flow_id, (from, to, name)) in self.flows().into_iter().enumerate() {
+            let (from, to) = (&self.spans[from], &self.spans[to]);
+            trace.push(json!({
+                "name": name, "cat": "flow", "ph": "s", "id": flow_id,
+                "ts": ts(from.start), "pid": PID, "tid": from.id,
+            }));
+            trace.push(json!({
+                "name": name, "cat": "flow", "ph": "f", "bp": "e", "id": flow_id,
+                "ts": ts(to.start), "pid": PID, "tid": to.id,
+            }));
+        }
+
+        for (span, name) in self.instants() {
+            let span = &self.spans[span];
+            trace.push(json!({
+                "name": name, "cat": "event", "ph": "i", "s": "t",
+                "ts": ts(span.start + span.duration), "pid": PID, "tid": span.id,
+            }));
+        }
+
+        json!({ "traceEvents": trace, "displayTimeUnit": "ns" })
+    }
+
+    /// Write the trace as JSON.
+    /// # Errors
+    /// If writing fails.
+    pub fn write(&self, writer: impl Write) -> io::Result<()> {
+        serde_json::to_writer(writer, &self.to_json()).map_err(io::Error::from)
+    }
+
+    /// Pairs of span indexes where the first drone passed the packet to the second one.
+    fn flows(&self) -> Vec<(usize, usize, &'static str)> {
+        let mut flows = vec![];
+        // Spans seen so far
+        let mut earlier = HashMap::<SpanKey, Vec<usize>>::new();
+        for (to, span) in self.spans.iter().enumerate() {
+            let (session, id) = packet_key(&span.packet);
+            let origin = sender_of(&span.packet)
+                .and_then(|sender| earlier.get(&(sender, session, id)))
+                .and_then(|candidates| {
+                    candidates.iter().rev().find_map(|&from| {
+                        let previous = &self.spans[from];
+                        let name = origin_of(&previous.packet, previous.id, &span.packet)?;
+                        Some((from, name))
+                    })
+                });
+            if let Some((from, name)) = origin {
+                flows.push((from, to, name));
+            }
+            earlier.entry((span.id, session, id)).or_default().push(to);
+        }
+        flows
+    }
+
+    /// Span indexes of drops, nacks and shortcuts, with their name.
+    /// Events of a drone are in order, so each one is matched to the first span not matched yet.
+    fn instants(&self) -> Vec<(usize, String)> {
+        let mut spans = HashMap::<SpanKey, Vec<usize>>::new();
+        for (i, span) in self.spans.iter().enumerate() {
+            let (session, id) = packet_key(&span.packet);
+            spans.entry((span.id, session, id)).or_default().push(i);
+        }
+
+        let mut used = HashSet::new();
+        let mut instants = vec![];
+        for (drone, event) in &self.events {
+            let (kind, packet) = match event {
+                DroneEvent::PacketDropped(packet) => ("drop", packet),
+                DroneEvent::ControllerShortcut(packet) => ("shortcut", packet),
+                DroneEvent::PacketSent(packet) => match packet.pack_type {
+                    // A nack that starts from the drone was created by it
+                    PacketType::Nack(_) if packet.routing_header.hops.first() == Some(drone) => {
+                        ("nack", packet)
+                    }
+                    _ => continue,
+                },
+            };
+
+            let (session, id) = packet_key(packet);
+            let Some(candidates) = spans.get(&(*drone, session, id)) else {
+                continue;
+            };
+            let span = candidates.iter().copied().find(|&i| {
+                let span = &self.spans[i].packet;
+                !used.contains(&(i, kind))
+                    && (span == packet || forwarded(span, packet) || created_from(span, packet))
+            });
+            if let Some(span) = span {
+                used.insert((span, kind));
+                let name = match packet.pack_type {
+                    PacketType::Nack(ref nack) if kind == "nack" => {
+                        format!("nack {:?}", nack.nack_type)
+                    }
+                    _ => kind.to_string(),
+                };
+                instants.push((span, name));
+            }
+        }
+        instants
+    }
+}
+
+/// Name of the flow if `previous`, handled by `drone`, is where `packet` comes from.
+fn origin_of(previous: &Packet, drone: NodeId, packet: &Packet) -> Option<&'static str> {
+    if let PacketType::FloodRequest(ref flood) = packet.pack_type {
+        let PacketType::FloodRequest(ref before) = previous.pack_type else {
+            return None;
+        };
+        let sent_by_drone = flood.path_trace.last().map(|(id, _)| *id) == Some(drone);
+        let same_flood =
+            before.flood_id == flood.flood_id && before.initiator_id == flood.initiator_id;
+        return (sent_by_drone
+            && same_flood
+            && before.path_trace.len() + 1 == flood.path_trace.len())
+        .then_some("flood");
+    }
+
+    let routing = &packet.routing_header;
+    let sender = routing.previous_hop()?;
+    if sender != drone {
+        return None;
+    }
+    if routing.hop_index == 1 && routing.hops.first() == Some(&drone) {
+        return created_from(previous, packet).then_some(match packet.pack_type {
+            PacketType::Nack(_) => "nack",
+            _ => "response",
+        });
+    }
+
+    forwarded(previous, packet).then_some("forward")
+}
+
+/// Session (not for floods, whose responses may use another one) and fragment or flood id.
+fn packet_key(packet: &Packet) -> (Option<u64>, u64) {
+    match &packet.pack_type {
+        PacketType::MsgFragment(fragment) => (Some(packet.session_id), fragment.fragment_index),
+        PacketType::Ack(ack) => (Some(packet.session_id), ack.fragment_index),
+        PacketType::Nack(nack) => (Some(packet.session_id), nack.fragment_index),
+        PacketType::FloodRequest(flood) => (None, flood.flood_id),
+        PacketType::FloodResponse(flood) => (None, flood.flood_id),
+    }
+}
+
+/// Drone that handed the packet over, as in [`origin_of`].
+fn sender_of(packet: &Packet) -> Option<NodeId> {
+    match packet.pack_type {
+        PacketType::FloodRequest(ref flood) => flood.path_trace.last().map(|(id, _)| *id),
+        _ => packet.routing_header.previous_hop(),
+    }
+}
+
+/// If `packet` is `previous` forwarded to the next hop.
+fn forwarded(previous: &Packet, packet: &Packet) -> bool {
+    previous.session_id == packet.session_id
+        && previous.routing_header.hop_index + 1 == packet.routing_header.hop_index
+        && previous.routing_header.hops == packet.routing_header.hops
+        && previous.pack_type == packet.pack_type
+}
+
+/// If the drone handling `previous` created `packet` as its response.
+fn created_from(previous: &Packet, packet: &Packet) -> bool {
+    match (&previous.pack_type, &packet.pack_type) {
+        (PacketType::MsgFragment(fragment), PacketType::Nack(nack)) => {
+            previous.session_id == packet.session_id
+                && fragment.fragment_index == nack.fragment_index
+        }
+        (PacketType::FloodRequest(request), PacketType::FloodResponse(response)) => {
+            request.flood_id == response.flood_id
+        }
+        _ => false,
+    }
+}
+
+fn span_name(packet: &Packet) -> String {
+    match &packet.pack_type {
+        PacketType::MsgFragment(fragment) => format!(
+            "MsgFragment {}/{}",
+            fragment.fragment_index, fragment.total_n_fragments
+        ),
+        PacketType::Ack(ack) => format!("Ack {}", ack.fragment_index),
+        PacketType::Nack(nack) => format!("Nack {}", nack.fragment_index),
+        PacketType::FloodRequest(flood) => format!("FloodRequest {}", flood.flood_id),
+        PacketType::FloodResponse(flood) => format!("FloodResponse {}", flood.flood_id),
+    }
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
@@ -0,0 +1,70 @@
+use crate::transport::Transport;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::{Arc, Mutex};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Crossbeam channels between threads of the same process, addressed by `NodeId`.
+/// Clones share the same nodes.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelTransport {
+    nodes: Arc<Mutex<HashMap<NodeId, Sender<Packet>>>>,
+}
+
+impl ChannelTransport {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Transport for ChannelTransport {
+    type Address = NodeId;
+
+    fn listen(&self, address: &NodeId) -> io::Result<(NodeId, Receiver<Packet>)> {
+        let mut nodes = self
+            .nodes
+            .lock()
+            .map_err(|_| io::Error::other("poisoned"))?;
+        if nodes.contains_key(address) {
+            return Err(io::Error::new(
+                ErrorKind::AddrInUse,
+                format!("node {address} is already listening"),
+            ));
+        }
+
+        let (packet_send, packet_recv) = unbounded();
+        nodes.insert(*address, packet_send);
+        Ok((*address, packet_recv))
+    }
+
+    fn connect(&self, address: &NodeId) -> io::Result<Sender<Packet>> {
+        let nodes = self
+            .nodes
+            .lock()
+            .map_err(|_| io::Error::other("poisoned"))?;
+        nodes.get(address).cloned().ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("node {address} is not listening"),
+            )
+        })
+    }
+
+    /// Nodes already connected can still send to the channel.
+    fn close(&self, address: &NodeId) -> io::Result<()> {
+        let mut nodes = self
+            .nodes
+            .lock()
+            .map_err(|_| io::Error::other("poisoned"))?;
+        nodes.remove(address).map(drop).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("node {address} is not listening"),
+            )
+        })
+    }
+}
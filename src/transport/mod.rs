@@ -0,0 +1,192 @@
+//! Ways packets travel between nodes: in-process channels or local sockets.
+//!
+//! A drone always sends and receives through crossbeam channels, as required by
+//! [`wg_2024::drone::Drone`]. A socket [`Transport`] moves the packets between those channels
+//! and its sockets on background threads, encoded with the [`codec`](crate::codec), so the same
+//! forwarding logic runs whether the neighbors are threads or other processes.
+
+mod channel;
+mod test;
+mod udp;
+#[cfg(unix)]
+mod unix;
+
+pub use channel::ChannelTransport;
+pub use udp::UdpTransport;
+#[cfg(unix)]
+pub use unix::UnixTransport;
+
+use crate::codec;
+use crate::drone::DroneBuilder;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// How often a receiving thread checks if it must stop while no datagram arrives.
+const RECV_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Medium connecting the packet channels of the nodes.
+pub trait Transport {
+    /// Where a node can be reached.
+    type Address;
+
+    /// Start receiving the packets sent to `address`.
+    /// # Returns
+    /// The address actually listened on (with the chosen port if it was left to the system)
+    /// and the channel to give to the node as its packet receiver.
+    /// # Errors
+    /// If the address cannot be listened on.
+    fn listen(&self, address: &Self::Address) -> io::Result<(Self::Address, Receiver<Packet>)>;
+
+    /// Channel sending packets to the node listening on `address`.
+    /// # Errors
+    /// If no channel toward the address can be opened.
+    fn connect(&self, address: &Self::Address) -> io::Result<Sender<Packet>>;
+
+    /// Stop listening on `address`, which can then be listened on again. The channel returned
+    /// by [`Transport::listen`] is disconnected once every sender toward it is gone: for socket
+    /// transports, right after its queued packets.
+    /// # Errors
+    /// If nobody listens on the address.
+    fn close(&self, address: &Self::Address) -> io::Result<()>;
+
+    /// Give the drone its packet receiver on `address` and a sender toward every neighbor.
+    /// # Returns
+    /// The builder and the address actually listened on.
+    /// # Errors
+    /// If listening or connecting to a neighbor fails.
+    fn configure(
+        &self,
+        mut builder: DroneBuilder,
+        address: &Self::Address,
+        neighbors: &HashMap<NodeId, Self::Address>,
+    ) -> io::Result<(DroneBuilder, Self::Address)> {
+        let (address, packet_recv) = self.listen(address)?;
+        builder = builder.packet_recv(packet_recv);
+        for (id, neighbor) in neighbors {
+            builder = builder.neighbor(*id, self.connect(neighbor)?);
+        }
+        Ok((builder, address))
+    }
+}
+
+/// Receiving threads of a socket transport, by address listened on.
+type Listeners<A> = Arc<Mutex<HashMap<A, Listener>>>;
+
+/// Thread receiving the datagrams of an address.
+#[derive(Debug)]
+struct Listener {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Listener {
+    /// Stop the thread and wait until it released its socket.
+    fn stop(self) -> io::Result<()> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread
+            .join()
+            .map_err(|_| io::Error::other("the receiving thread panicked"))
+    }
+}
+
+/// Decode the datagrams returned by `recv` on a new thread, invalid ones are ignored.
+/// `recv` must time out after [`RECV_TIMEOUT`] so that the thread notices when it is stopped.
+/// The thread also stops at the first receive error, or once a packet arrives after the channel
+/// was dropped.
+fn receive_datagrams(
+    mut recv: impl FnMut(&mut [u8]) -> io::Result<usize> + Send + 'static,
+) -> (Receiver<Packet>, Listener) {
+    let (packet_send, packet_recv) = unbounded();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    let thread = thread::spawn(move || {
+        let mut buf = vec![0; codec::MAX_FRAME_LEN];
+        while !stopped.load(Ordering::Relaxed) {
+            let len = match recv(&mut buf) {
+                Ok(len) => len,
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+                    ) =>
+                {
+                    continue;
+                }
+                Err(_) => return,
+            };
+            let Ok(packet) = codec::decode(&buf[..len]) else {
+                continue;
+            };
+            if packet_send.send(packet).is_err() {
+                return;
+            }
+        }
+    });
+    (packet_recv, Listener { stop, thread })
+}
+
+/// Keep the listener of `address`, stopping the previous one if any: its socket may still be
+/// open if its file was removed, or its thread may have stopped on its own.
+fn add_listener<A: Eq + Hash>(
+    listeners: &Listeners<A>,
+    address: A,
+    listener: Listener,
+) -> io::Result<()> {
+    let previous = listeners
+        .lock()
+        .map_err(|_| io::Error::other("poisoned"))?
+        .insert(address, listener);
+    previous.map_or(Ok(()), Listener::stop)
+}
+
+/// Stop the thread receiving on `address` and wait until it released its socket.
+fn stop_listener<A: Eq + Hash + Debug>(listeners: &Listeners<A>, address: &A) -> io::Result<()> {
+    let listener = listeners
+        .lock()
+        .map_err(|_| io::Error::other("poisoned"))?
+        .remove(address)
+        .ok_or_else(|| {
+            io::Error::new(ErrorKind::NotFound, format!("not listening on {address:?}"))
+        })?;
+    listener.stop()
+}
+
+/// Encode the packets of the channel and hand them to `send` on a new thread.
+/// Like on a real network, packets that cannot be sent or encoded are lost.
+/// The thread stops once every sender was dropped, or once `send` reports that nobody
+/// listens on the other side anymore: from then on the senders fail.
+fn send_datagrams(
+    mut send: impl FnMut(&[u8]) -> io::Result<usize> + Send + 'static,
+) -> Sender<Packet> {
+    let (packet_send, packet_recv) = unbounded::<Packet>();
+    thread::spawn(move || {
+        for packet in packet_recv {
+            let Ok(bytes) = codec::encode(&packet) else {
+                continue;
+            };
+            match send(&bytes) {
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        ErrorKind::ConnectionRefused | ErrorKind::NotFound
+                    ) =>
+                {
+                    return;
+                }
+                _ => {}
+            }
+        }
+    });
+    packet_send
+}
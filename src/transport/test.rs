@@ -0,0 +1,208 @@
+#![cfg(test)]
+
+use crate::codec;
+use crate::drone::{DrainPolicy, RustyDrone};
+use crate::transport::{ChannelTransport, Transport, UdpTransport};
+use crossbeam_channel::{unbounded, Receiver};
+use rusty_tester::utils::data::{new_forwarded, new_test_fragment_packet, new_test_nack};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+use std::time::Duration;
+use wg_2024::drone::Drone;
+use wg_2024::packet::{NackType, Packet};
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Run drone 11 between client 1 and drone 12, send a fragment from 1 and a nack from 12.
+/// # Returns
+/// What 12 and 1 received.
+fn forward_through_drone<T: Transport>(
+    transport: &T,
+    addresses: [T::Address; 3],
+) -> (Packet, Packet)
+where
+    T::Address: Clone,
+{
+    let [client, drone, next] = addresses;
+    let (client, client_recv) = transport.listen(&client).unwrap();
+    let (next, next_recv) = transport.listen(&next).unwrap();
+    let neighbors = HashMap::from([(1, client), (12, next)]);
+
+    let (command_send, command_recv) = unbounded();
+    let builder = RustyDrone::builder()
+        .id(11)
+        .controller_send(unbounded().0)
+        .controller_recv(command_recv)
+        .drain(DrainPolicy::UntilEmpty);
+    let (builder, drone) = transport.configure(builder, &drone, &neighbors).unwrap();
+    let mut running = builder.build().unwrap();
+    let thread = thread::spawn(move || running.run());
+
+    let drone_send = transport.connect(&drone).unwrap();
+    drone_send
+        .send(new_test_fragment_packet(&[1, 11, 12], 5))
+        .unwrap();
+    drone_send
+        .send(new_test_nack(&[12, 11, 1], NackType::Dropped, 7, 1))
+        .unwrap();
+    let received = (recv(&next_recv), recv(&client_recv));
+
+    drop(command_send);
+    thread.join().unwrap();
+    received
+}
+
+fn recv(packet_recv: &Receiver<Packet>) -> Packet {
+    packet_recv.recv_timeout(TIMEOUT).unwrap()
+}
+
+fn assert_forwarded((fragment, nack): (Packet, Packet)) {
+    let sent = new_test_fragment_packet(&[1, 11, 12], 5);
+    assert_eq!(fragment, new_forwarded(&sent));
+    let sent = new_test_nack(&[12, 11, 1], NackType::Dropped, 7, 1);
+    assert_eq!(nack, new_forwarded(&sent));
+}
+
+fn localhost() -> SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+#[test]
+fn test_transport_channel() {
+    let transport = ChannelTransport::new();
+    assert_forwarded(forward_through_drone(&transport, [1, 11, 12]));
+
+    assert_eq!(
+        transport.listen(&11).unwrap_err().kind(),
+        ErrorKind::AddrInUse
+    );
+    assert_eq!(
+        transport.connect(&13).unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+
+    transport.close(&11).unwrap();
+    assert_eq!(
+        transport.close(&11).unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+    assert!(transport.listen(&11).is_ok());
+}
+
+#[test]
+fn test_transport_udp() {
+    assert_forwarded(forward_through_drone(
+        &UdpTransport::new(),
+        [localhost(), localhost(), localhost()],
+    ));
+}
+
+#[test]
+fn test_transport_udp_invalid_datagrams() {
+    let (address, packet_recv) = UdpTransport::new().listen(&localhost()).unwrap();
+    let socket = UdpSocket::bind(localhost()).unwrap();
+    let packet = new_test_fragment_packet(&[1, 11], 5);
+    let mut truncated = codec::encode(&packet).unwrap();
+    truncated.pop();
+
+    socket.send_to(b"not a packet", address).unwrap();
+    socket.send_to(&truncated, address).unwrap();
+    UdpTransport::new()
+        .connect(&address)
+        .unwrap()
+        .send(packet.clone())
+        .unwrap();
+
+    assert_eq!(recv(&packet_recv), packet);
+    assert!(packet_recv.try_recv().is_err());
+}
+
+#[test]
+fn test_transport_udp_close() {
+    let transport = UdpTransport::new();
+    let (address, packet_recv) = transport.listen(&localhost()).unwrap();
+    assert_eq!(
+        transport.listen(&address).unwrap_err().kind(),
+        ErrorKind::AddrInUse
+    );
+
+    // The socket is released without waiting for another datagram
+    transport.close(&address).unwrap();
+    assert!(packet_recv.recv_timeout(TIMEOUT).is_err());
+    assert_eq!(
+        transport.close(&address).unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+    assert!(transport.listen(&address).is_ok());
+}
+
+#[test]
+fn test_transport_udp_closed() {
+    let socket = UdpSocket::bind(localhost()).unwrap();
+    let address = socket.local_addr().unwrap();
+    drop(socket);
+
+    // The first packets are lost until the host reports that nobody listens
+    let packet_send = UdpTransport::new().connect(&address).unwrap();
+    let closed = (0..50).any(|session| {
+        thread::sleep(Duration::from_millis(10));
+        packet_send
+            .send(new_test_fragment_packet(&[1, 11], session))
+            .is_err()
+    });
+    assert!(closed);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_transport_unix() {
+    use crate::transport::UnixTransport;
+    use std::os::unix::net::UnixDatagram;
+
+    let dir = std::env::temp_dir().join(format!("rusty_unix_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let addresses = ["1.sock", "11.sock", "12.sock"].map(|name| dir.join(name));
+    // A socket file left behind is replaced
+    drop(UnixDatagram::bind(&addresses[1]).unwrap());
+    // Any other file is kept
+    let file = dir.join("13.sock");
+    std::fs::write(&file, b"data").unwrap();
+
+    let received = forward_through_drone(&UnixTransport::new(), addresses);
+    let missing = UnixTransport::new().connect(&dir.join("14.sock"));
+    let not_socket = UnixTransport::new().listen(&file);
+    let kept = std::fs::read(&file);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_forwarded(received);
+    assert_eq!(missing.unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(not_socket.unwrap_err().kind(), ErrorKind::AddrInUse);
+    assert_eq!(kept.unwrap(), b"data");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_transport_unix_in_use() {
+    use crate::transport::UnixTransport;
+
+    let dir = std::env::temp_dir().join(format!("rusty_unix_in_use_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let address = dir.join("11.sock");
+    let transport = UnixTransport::new();
+    let (address, packet_recv) = transport.listen(&address).unwrap();
+
+    // The socket of a live listener is kept
+    let in_use = UnixTransport::new().listen(&address);
+    let packet = new_test_fragment_packet(&[1, 11, 12], 5);
+    transport
+        .connect(&address)
+        .unwrap()
+        .send(packet.clone())
+        .unwrap();
+    let received = packet_recv.recv_timeout(TIMEOUT);
+    transport.close(&address).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(in_use.unwrap_err().kind(), ErrorKind::AddrInUse);
+    assert_eq!(received.unwrap(), packet);
+}
@@ -0,0 +1,52 @@
+use crate::transport::{
+    add_listener, receive_datagrams, send_datagrams, stop_listener, Listeners, Transport,
+    RECV_TIMEOUT,
+};
+use crossbeam_channel::{Receiver, Sender};
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use wg_2024::packet::Packet;
+
+/// One UDP datagram per packet, meant for processes on the same host.
+/// Packets sent to an address nobody listens on are lost, until the host reports it:
+/// then the sender toward that address is closed.
+/// Clones share the addresses listened on.
+#[derive(Debug, Clone, Default)]
+pub struct UdpTransport {
+    listeners: Listeners<SocketAddr>,
+}
+
+impl UdpTransport {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Transport for UdpTransport {
+    type Address = SocketAddr;
+
+    fn listen(&self, address: &SocketAddr) -> io::Result<(SocketAddr, Receiver<Packet>)> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_read_timeout(Some(RECV_TIMEOUT))?;
+        let address = socket.local_addr()?;
+        let (packet_recv, listener) = receive_datagrams(move |buf| socket.recv(buf));
+        add_listener(&self.listeners, address, listener)?;
+        Ok((address, packet_recv))
+    }
+
+    fn connect(&self, address: &SocketAddr) -> io::Result<Sender<Packet>> {
+        let local: SocketAddr = if address.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(address)?;
+        Ok(send_datagrams(move |bytes| socket.send(bytes)))
+    }
+
+    fn close(&self, address: &SocketAddr) -> io::Result<()> {
+        stop_listener(&self.listeners, address)
+    }
+}
@@ -0,0 +1,77 @@
+use crate::transport::{
+    add_listener, receive_datagrams, send_datagrams, stop_listener, Listeners, Transport,
+    RECV_TIMEOUT,
+};
+use crossbeam_channel::{Receiver, Sender};
+use std::fs;
+use std::io;
+use std::io::ErrorKind;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+use wg_2024::packet::Packet;
+
+/// One Unix domain datagram per packet, addressed by socket path.
+/// Connecting to a path nobody listens on fails, the sender is closed once the node
+/// stops listening.
+/// Clones share the paths listened on.
+#[derive(Debug, Clone, Default)]
+pub struct UnixTransport {
+    listeners: Listeners<PathBuf>,
+}
+
+impl UnixTransport {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Transport for UnixTransport {
+    type Address = PathBuf;
+
+    /// A socket file left by a node that did not exit cleanly, which refuses connections, is
+    /// replaced. A socket still bound or any other file makes the address in use.
+    fn listen(&self, address: &PathBuf) -> io::Result<(PathBuf, Receiver<Packet>)> {
+        match fs::symlink_metadata(address) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                match UnixDatagram::unbound()?.connect(address) {
+                    Err(err) if err.kind() == ErrorKind::ConnectionRefused => {
+                        fs::remove_file(address)?;
+                    }
+                    _ => {
+                        return Err(io::Error::new(
+                            ErrorKind::AddrInUse,
+                            format!("{} is still bound", address.display()),
+                        ))
+                    }
+                }
+            }
+            Ok(_) => {
+                return Err(io::Error::new(
+                    ErrorKind::AddrInUse,
+                    format!("{} is not a socket", address.display()),
+                ))
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        let socket = UnixDatagram::bind(address)?;
+        socket.set_read_timeout(Some(RECV_TIMEOUT))?;
+        let (packet_recv, listener) = receive_datagrams(move |buf| socket.recv(buf));
+        add_listener(&self.listeners, address.clone(), listener)?;
+        Ok((address.clone(), packet_recv))
+    }
+
+    fn connect(&self, address: &PathBuf) -> io::Result<Sender<Packet>> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(address)?;
+        Ok(send_datagrams(move |bytes| socket.send(bytes)))
+    }
+
+    /// The socket file is removed.
+    fn close(&self, address: &PathBuf) -> io::Result<()> {
+        stop_listener(&self.listeners, address)?;
+        fs::remove_file(address)
+    }
+}
@@ -0,0 +1,216 @@
+//! Drones running as real processes, started by the `rusty-drone` binary.
+
+use rusty_drones::process::{LaunchError, Launcher, ProcessNetwork};
+use rusty_drones::simulation::{parse_config, Topology};
+use rusty_tester::utils::data::new_test_fragment_packet;
+use std::path::Path;
+use std::time::Duration;
+use wg_2024::config::{Config, Drone};
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+/// How long to wait for a packet that should not arrive.
+const SILENCE: Duration = Duration::from_millis(300);
+
+/// Client 1 - drone 11 - drone 12 - server 21.
+/// Launcher logging in the temporary directory of the test target.
+fn launcher(test: &str) -> Launcher {
+    Launcher::new(env!("CARGO_BIN_EXE_rusty-drone"))
+        .log_dir(Path::new(env!("CARGO_TARGET_TMPDIR")).join(test))
+}
+
+fn chain(test: &str) -> ProcessNetwork {
+    let mut topology = Topology::new();
+    topology
+        .add_client(1)
+        .add_drone(11, 0.0)
+        .add_drone(12, 0.0)
+        .add_server(21)
+        .connect(1, 11)
+        .connect(11, 12)
+        .connect(12, 21);
+    launcher(test).seed(1).launch(&topology).unwrap()
+}
+
+fn fragment(session: u64) -> Packet {
+    new_test_fragment_packet(&[1, 11, 12, 21], session)
+}
+
+/// Wait for a `PacketSent` of the session by the drone.
+fn wait_sent(network: &ProcessNetwork, drone: NodeId, session: u64) {
+    while let Some((id, event)) = network.next_event(TIMEOUT) {
+        if let DroneEvent::PacketSent(packet) = event {
+            if id == drone && packet.session_id == session {
+                return;
+            }
+        }
+    }
+    panic!("drone {drone} did not send session {session}");
+}
+
+#[test]
+fn test_process_forwarding() {
+    let network = chain("forwarding");
+    assert_eq!(network.drone_ids().collect::<Vec<_>>(), [11, 12]);
+
+    network.send_from(1, fragment(5)).unwrap();
+
+    let received = network.packet_recv(21).unwrap().recv_timeout(TIMEOUT);
+    assert_eq!(received.unwrap().routing_header.hop_index, 3);
+    wait_sent(&network, 12, 5);
+}
+
+#[test]
+fn test_process_kill_and_restart() {
+    let mut network = chain("kill");
+    let address = network.address(12);
+
+    network.kill(12).unwrap();
+    assert!(!network.is_running(12).unwrap());
+
+    // Drone 11 does not know and keeps sending, the packet is lost
+    network.send_from(1, fragment(5)).unwrap();
+    wait_sent(&network, 11, 5);
+    let server = network.packet_recv(21).unwrap();
+    assert!(server.recv_timeout(SILENCE).is_err());
+
+    network.restart(12).unwrap();
+    assert!(network.is_running(12).unwrap());
+    assert_eq!(network.address(12), address);
+    network.send_from(1, fragment(6)).unwrap();
+    let received = network.packet_recv(21).unwrap().recv_timeout(TIMEOUT);
+    assert_eq!(received.unwrap().session_id, 6);
+
+    let logs = network.logs(12).unwrap();
+    assert_eq!(logs.matches("listening on").count(), 2);
+    assert!(logs.contains("launcher: drone 12 killed"));
+}
+
+#[test]
+fn test_process_killed_neighbor() {
+    let mut network = chain("killed");
+    network.kill(12).unwrap();
+
+    // Drone 11 loses the first packets, until the host reports that 12 is gone
+    let client = network.packet_recv(1).unwrap();
+    let nack = (5..20).find_map(|session| {
+        network.send_from(1, fragment(session)).unwrap();
+        client.recv_timeout(SILENCE).ok()
+    });
+    assert!(matches!(
+        nack.unwrap().pack_type,
+        PacketType::Nack(ref nack) if nack.nack_type == NackType::ErrorInRouting(12)
+    ));
+    assert!(network.packet_recv(21).unwrap().try_recv().is_err());
+}
+
+#[test]
+fn test_process_shortcut() {
+    let mut network = chain("shortcut");
+    network.kill(11).unwrap();
+
+    // Once drone 12 knows that 11 is gone, the acks reach the client through the shortcut
+    let client = network.packet_recv(1).unwrap();
+    let ack = (5..20).find_map(|session| {
+        let ack = Packet::new_ack(SourceRoutingHeader::new(vec![21, 12, 11, 1], 1), session, 0);
+        network.send_from(21, ack).unwrap();
+        client.recv_timeout(SILENCE).ok()
+    });
+    assert!(matches!(ack.unwrap().pack_type, PacketType::Ack(_)));
+    let shortcut = std::iter::from_fn(|| network.next_event(TIMEOUT))
+        .any(|(id, event)| id == 12 && matches!(event, DroneEvent::ControllerShortcut(_)));
+    assert!(shortcut);
+}
+
+#[test]
+fn test_process_restart_with_killed_drone() {
+    let mut network = chain("killed_other");
+
+    // The dead drone 11 does not prevent 12 from coming back
+    network.kill(11).unwrap();
+    network.restart(12).unwrap();
+    assert!(network.is_running(12).unwrap());
+    assert!(!network.is_running(11).unwrap());
+}
+
+#[test]
+fn test_process_restart_exited() {
+    let mut network = chain("restart");
+
+    network.kill(11).unwrap();
+    network.crash(12).unwrap();
+    let status = network.wait(12, TIMEOUT).unwrap();
+    assert!(status.is_some_and(|status| status.success()));
+
+    // Only the drone that died without a crash command comes back
+    assert_eq!(network.restart_exited().unwrap(), [11]);
+    assert!(network.is_running(11).unwrap());
+    assert!(!network.is_running(12).unwrap());
+    assert!(network.logs(12).unwrap().contains("stopped, Crashed"));
+}
+
+#[test]
+fn test_process_crash_notifies_neighbors() {
+    let mut network = chain("crash");
+
+    network.crash(12).unwrap();
+    assert!(matches!(network.crash(12), Err(LaunchError::Simulation(_))));
+
+    // Drone 11 removed 12 and tells the client
+    network.send_from(1, fragment(5)).unwrap();
+    let nack = network
+        .packet_recv(1)
+        .unwrap()
+        .recv_timeout(TIMEOUT)
+        .unwrap();
+    assert!(matches!(
+        nack.pack_type,
+        PacketType::Nack(ref nack) if nack.nack_type == NackType::ErrorInRouting(12)
+    ));
+}
+
+#[test]
+fn test_process_launch_config() {
+    let config = parse_config(
+        "[[drone]]\nid = 11\nconnected_node_ids = [1, 12, 21]\npdr = 0.0\n\
+         [[drone]]\nid = 12\nconnected_node_ids = [11, 21]\npdr = 0.0\n\
+         [[client]]\nid = 1\nconnected_drone_ids = [11]\n\
+         [[server]]\nid = 21\nconnected_drone_ids = [11, 12]\n",
+    )
+    .unwrap();
+    let network = launcher("config").launch_config(&config).unwrap();
+
+    network
+        .send_from(1, new_test_fragment_packet(&[1, 11, 21], 5))
+        .unwrap();
+    assert!(network
+        .packet_recv(21)
+        .unwrap()
+        .recv_timeout(TIMEOUT)
+        .is_ok());
+}
+
+#[test]
+fn test_process_launch_errors() {
+    // Drone linked to itself
+    let config = Config {
+        drone: vec![Drone {
+            id: 11,
+            connected_node_ids: vec![11],
+            pdr: 0.0,
+        }],
+        client: vec![],
+        server: vec![],
+    };
+    assert!(matches!(
+        launcher("errors").launch_config(&config),
+        Err(LaunchError::Config(_))
+    ));
+
+    let mut topology = Topology::new();
+    topology.add_drone(11, 0.0);
+    let missing = Launcher::new("/nonexistent/rusty-drone").log_dir(env!("CARGO_TARGET_TMPDIR"));
+    assert!(matches!(missing.launch(&topology), Err(LaunchError::Io(_))));
+}